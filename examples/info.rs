@@ -2,4 +2,4 @@ use norse_physical_device as physical_device;
 
 fn main() {
     println!("{:#?}", physical_device::PhysicalDeviceProperties::system());
-}
\ No newline at end of file
+}
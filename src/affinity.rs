@@ -0,0 +1,107 @@
+//! Helpers for running code pinned to specific logical processors.
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+use std::thread;
+
+/// Pin the current thread to the logical processor `cpu`.
+#[cfg(all(target_os = "linux", any(target_arch = "x86", target_arch = "x86_64")))]
+fn pin_current_thread(cpu: usize) -> bool {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(cpu, &mut set);
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) == 0
+    }
+}
+
+/// Pin the current thread to the logical processor `cpu`.
+#[cfg(all(
+    target_os = "windows",
+    any(target_arch = "x86", target_arch = "x86_64")
+))]
+fn pin_current_thread(cpu: usize) -> bool {
+    use winapi::um::processthreadsapi::GetCurrentThread;
+    use winapi::um::winbase::SetThreadAffinityMask;
+
+    if cpu >= usize::BITS as usize {
+        return false;
+    }
+    unsafe { SetThreadAffinityMask(GetCurrentThread(), 1 << cpu) != 0 }
+}
+
+/// Pin the current thread to the logical processor `cpu`.
+#[cfg(all(
+    not(any(target_os = "linux", target_os = "windows")),
+    any(target_arch = "x86", target_arch = "x86_64")
+))]
+fn pin_current_thread(_cpu: usize) -> bool {
+    false
+}
+
+/// Run `f` on each of the logical processors in `cpus`.
+///
+/// The code is executed on a separate thread to keep the affinity
+/// of the calling thread untouched. Processors the thread couldn't be
+/// pinned to are skipped, returns `None` if pinning failed for all of them.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub(crate) fn on_each_cpu<R, F>(cpus: &[usize], f: F) -> Option<Vec<(usize, R)>>
+where
+    R: Send,
+    F: Fn() -> R + Send,
+{
+    let results = thread::scope(|scope| {
+        scope
+            .spawn(move || {
+                cpus.iter()
+                    .filter(|&&cpu| pin_current_thread(cpu))
+                    .map(|&cpu| (cpu, f()))
+                    .collect::<Vec<_>>()
+            })
+            .join()
+            .ok()
+    })?;
+
+    if results.is_empty() {
+        None
+    } else {
+        Some(results)
+    }
+}
+
+/// Probe whether the thread affinity can be changed by setting the current mask again.
+#[cfg(target_os = "linux")]
+pub(crate) fn affinity_supported() -> bool {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        let size = std::mem::size_of::<libc::cpu_set_t>();
+        libc::sched_getaffinity(0, size, &mut set) == 0
+            && libc::sched_setaffinity(0, size, &set) == 0
+    }
+}
+
+/// Probe whether the thread affinity can be changed by setting the current mask again.
+#[cfg(target_os = "windows")]
+pub(crate) fn affinity_supported() -> bool {
+    use winapi::um::processthreadsapi::{GetCurrentProcess, GetCurrentThread};
+    use winapi::um::winbase::{GetProcessAffinityMask, SetThreadAffinityMask};
+
+    unsafe {
+        let mut process_mask = 0;
+        let mut system_mask = 0;
+        if GetProcessAffinityMask(GetCurrentProcess(), &mut process_mask, &mut system_mask) == 0 {
+            return false;
+        }
+        // Returns the previous mask of the thread, which is restored afterwards.
+        let previous = SetThreadAffinityMask(GetCurrentThread(), process_mask);
+        if previous == 0 {
+            return false;
+        }
+        SetThreadAffinityMask(GetCurrentThread(), previous);
+        true
+    }
+}
+
+/// Probe whether the thread affinity can be changed by setting the current mask again.
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+pub(crate) fn affinity_supported() -> bool {
+    false
+}
@@ -0,0 +1,82 @@
+//! Run blocking OS queries off the calling thread, independent of the async runtime.
+
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+type TaskResult<T> = thread::Result<T>;
+
+struct State<T> {
+    result: Option<TaskResult<T>>,
+    waker: Option<Waker>,
+}
+
+/// Future resolving to the result of a closure running on a dedicated thread.
+pub(crate) struct Blocking<T> {
+    state: Arc<Mutex<State<T>>>,
+}
+
+/// Run `f` on a new thread, the returned future resolves once it finished.
+///
+/// Panics of `f` are resumed when polling the future.
+pub(crate) fn spawn<T, F>(f: F) -> Blocking<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let state = Arc::new(Mutex::new(State {
+        result: None,
+        waker: None,
+    }));
+
+    // Threads aren't available on WebAssembly, the detection doesn't block there.
+    #[cfg(target_family = "wasm")]
+    {
+        state.lock().unwrap().result = Some(panic::catch_unwind(AssertUnwindSafe(f)));
+    }
+    #[cfg(not(target_family = "wasm"))]
+    {
+        let task_state = state.clone();
+        let spawned = thread::Builder::new()
+            .name("norse-physical-device".into())
+            .spawn(move || {
+                let result = panic::catch_unwind(AssertUnwindSafe(f));
+                let waker = {
+                    let mut state = task_state.lock().unwrap();
+                    state.result = Some(result);
+                    state.waker.take()
+                };
+                if let Some(waker) = waker {
+                    waker.wake();
+                }
+            });
+        if let Err(err) = spawned {
+            // Resumed as panic when polled, like a panic of `f`.
+            state.lock().unwrap().result = Some(Err(Box::new(err)));
+        }
+    }
+
+    Blocking { state }
+}
+
+impl<T> Future for Blocking<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(Ok(value)) => Poll::Ready(value),
+            Some(Err(payload)) => {
+                drop(state);
+                panic::resume_unwind(payload)
+            }
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
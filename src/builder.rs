@@ -0,0 +1,166 @@
+use crate::properties::{
+    CoreCluster, CoreComplex, Endianness, Hypervisor, NumaNode, PhysicalDeviceCacheProperties,
+    PhysicalDeviceProperties, PhysicalDeviceTlbProperties, PmuInfo, SecurityFeatures, Translation,
+    Vendor,
+};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Builder for synthetic `PhysicalDeviceProperties`, e.g. for testing.
+///
+/// Fields which are not set are `0`, empty or unknown,
+/// except for endianness and pointer width which default to the compilation target.
+#[derive(Debug, Clone)]
+pub struct PhysicalDevicePropertiesBuilder {
+    properties: PhysicalDeviceProperties,
+}
+
+macro_rules! setters {
+    ($($(#[$doc:meta])* $field:ident: $ty:ty,)*) => {
+        $(
+            $(#[$doc])*
+            pub fn $field(mut self, $field: $ty) -> Self {
+                self.properties.$field = $field.into();
+                self
+            }
+        )*
+    };
+}
+
+impl PhysicalDevicePropertiesBuilder {
+    pub(crate) fn new() -> Self {
+        PhysicalDevicePropertiesBuilder {
+            properties: PhysicalDeviceProperties {
+                vendor: Vendor::Unknown,
+                device: String::new(),
+                family: 0,
+                model: 0,
+                stepping: 0,
+                microarchitecture: None,
+                logical_cores: 0,
+                physical_cores: 0,
+                threads_per_core: 0,
+                package_count: 1,
+                l1_cache_data: Default::default(),
+                l1_cache_instruction: Default::default(),
+                l1_cache_unified: None,
+                l2_cache: Default::default(),
+                l3_cache: Default::default(),
+                l4_cache: Default::default(),
+                recommended_prefetch_bytes: 0,
+                l1_tlb_data: Default::default(),
+                l1_tlb_instruction: Default::default(),
+                l2_tlb: Default::default(),
+                base_frequency_mhz: 0,
+                max_frequency_mhz: 0,
+                current_frequency_mhz: 0,
+                turbo_enabled: None,
+                invariant_tsc: false,
+                tdp_watts: None,
+                total_memory_bytes: 0,
+                available_memory_bytes: 0,
+                large_page_sizes: Vec::new(),
+                core_clusters: Vec::new(),
+                core_complexes: Vec::new(),
+                numa_nodes: Vec::new(),
+                hypervisor: None,
+                translation: None,
+                security_features: SecurityFeatures::default(),
+                pmu_counters: PmuInfo::default(),
+                endianness: Endianness::native(),
+                pointer_width_bits: usize::BITS as u8,
+            },
+        }
+    }
+
+    setters! {
+        /// Device Hardware Vendor.
+        vendor: Vendor,
+        /// Name of the device.
+        device: impl Into<String>,
+        /// Processor family.
+        family: u32,
+        /// Processor model.
+        model: u32,
+        /// Processor stepping.
+        stepping: u32,
+        /// Microarchitecture name.
+        microarchitecture: Option<String>,
+        /// Number of logical cores.
+        logical_cores: usize,
+        /// Number of physical cores.
+        physical_cores: usize,
+        /// Number of hardware threads per physical core.
+        threads_per_core: usize,
+        /// Number of physical packages (sockets).
+        package_count: usize,
+        /// Properties of the L1 Data Cache.
+        l1_cache_data: PhysicalDeviceCacheProperties,
+        /// Properties of the L1 Instruction Cache.
+        l1_cache_instruction: PhysicalDeviceCacheProperties,
+        /// Properties of the L1 Unified Cache, for devices not splitting the L1 cache.
+        l1_cache_unified: Option<PhysicalDeviceCacheProperties>,
+        /// Properties of the L2 Unified Cache.
+        l2_cache: PhysicalDeviceCacheProperties,
+        /// Properties of the L3 Unified Cache.
+        l3_cache: PhysicalDeviceCacheProperties,
+        /// Properties of the L4 Unified Cache.
+        l4_cache: PhysicalDeviceCacheProperties,
+        /// Recommended software prefetch distance in bytes.
+        recommended_prefetch_bytes: u32,
+        /// Properties of the L1 Data TLB.
+        l1_tlb_data: PhysicalDeviceTlbProperties,
+        /// Properties of the L1 Instruction TLB.
+        l1_tlb_instruction: PhysicalDeviceTlbProperties,
+        /// Properties of the L2 (shared) TLB.
+        l2_tlb: PhysicalDeviceTlbProperties,
+        /// Base frequency in MHz.
+        base_frequency_mhz: u32,
+        /// Maximum frequency in MHz.
+        max_frequency_mhz: u32,
+        /// Current frequency in MHz.
+        current_frequency_mhz: u32,
+        /// Whether turbo boost is enabled.
+        turbo_enabled: Option<bool>,
+        /// Whether the time stamp counter is invariant.
+        invariant_tsc: bool,
+        /// Thermal design power of a package in watts.
+        tdp_watts: Option<u32>,
+        /// Total physical memory in bytes.
+        total_memory_bytes: u64,
+        /// Available physical memory in bytes.
+        available_memory_bytes: u64,
+        /// Supported page sizes beyond the base page size.
+        large_page_sizes: Vec<u64>,
+        /// Core clusters of hybrid architectures.
+        core_clusters: Vec<CoreCluster>,
+        /// Core complexes sharing an L3 slice.
+        core_complexes: Vec<CoreComplex>,
+        /// NUMA nodes.
+        numa_nodes: Vec<NumaNode>,
+        /// Hypervisor the device is running in.
+        hypervisor: Option<Hypervisor>,
+        /// Binary translator the process is running in.
+        translation: Option<Translation>,
+        /// Confidential computing and memory encryption capabilities.
+        security_features: SecurityFeatures,
+        /// Performance monitoring counters.
+        pmu_counters: PmuInfo,
+        /// Byte order.
+        endianness: Endianness,
+        /// Width of a pointer in bits.
+        pointer_width_bits: u8,
+    }
+
+    /// Build the properties.
+    pub fn build(self) -> PhysicalDeviceProperties {
+        self.properties
+    }
+}
+
+impl PhysicalDeviceProperties {
+    /// Create a builder for synthetic properties.
+    pub fn builder() -> PhysicalDevicePropertiesBuilder {
+        PhysicalDevicePropertiesBuilder::new()
+    }
+}
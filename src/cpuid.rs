@@ -0,0 +1,855 @@
+//! Decoding of x86 CPUID leaves.
+
+use crate::properties::{
+    CacheInclusivity, CacheKind, CacheWritePolicy, PhysicalDeviceCacheProperties,
+    PhysicalDeviceTlbProperties, PmuInfo, SecurityFeatures,
+};
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(target_arch = "x86")]
+pub(crate) use core::arch::x86 as arch;
+#[cfg(target_arch = "x86_64")]
+pub(crate) use core::arch::x86_64 as arch;
+
+use arch::{__cpuid, __cpuid_count, CpuidResult};
+use core::sync::atomic::{AtomicU8, Ordering};
+
+const PAGE_4K: u32 = 4 * 1024;
+
+/// Log the registers of a CPUID leaf.
+#[allow(unused_variables)]
+pub(crate) fn trace_leaf(leaf: u32, subleaf: u32, result: &CpuidResult) {
+    trace!(
+        leaf = format_args!("{:#x}", leaf),
+        subleaf,
+        eax = format_args!("{:#010x}", result.eax),
+        ebx = format_args!("{:#010x}", result.ebx),
+        ecx = format_args!("{:#010x}", result.ecx),
+        edx = format_args!("{:#010x}", result.edx),
+        "cpuid"
+    );
+}
+
+/// Returns if the `CPUID` instruction can be executed.
+///
+/// Checks the ID bit of EFLAGS on 32-bit x86 (CPUID is part of x86_64) and, on Linux,
+/// if CPUID faulting is enabled for the process (`arch_prctl(ARCH_SET_CPUID)`), which
+/// makes CPUID raise `SIGSEGV` in some sandboxes. The result is probed once and cached.
+pub fn cpuid_available() -> bool {
+    const UNPROBED: u8 = 0;
+    const AVAILABLE: u8 = 1;
+    const UNAVAILABLE: u8 = 2;
+    static STATE: AtomicU8 = AtomicU8::new(UNPROBED);
+
+    match STATE.load(Ordering::Relaxed) {
+        AVAILABLE => true,
+        UNAVAILABLE => false,
+        _ => {
+            let available = probe_cpuid();
+            if !available {
+                warn!("CPUID unavailable, CPUID derived properties are reported as unknown");
+            }
+            let state = if available { AVAILABLE } else { UNAVAILABLE };
+            STATE.store(state, Ordering::Relaxed);
+            available
+        }
+    }
+}
+
+fn probe_cpuid() -> bool {
+    #[cfg(target_arch = "x86")]
+    if !eflags_id_settable() {
+        return false;
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        const ARCH_GET_CPUID: libc::c_int = 0x1011;
+        #[cfg(target_arch = "x86")]
+        const SYS_ARCH_PRCTL: libc::c_long = 384;
+        #[cfg(target_arch = "x86_64")]
+        const SYS_ARCH_PRCTL: libc::c_long = libc::SYS_arch_prctl;
+
+        // Returns `0` if CPUID faults, kernels before 4.12 fail with `EINVAL` or `ENOSYS`.
+        let enabled = unsafe { libc::syscall(SYS_ARCH_PRCTL, ARCH_GET_CPUID, 0) };
+        if enabled == 0 {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Returns if the ID bit (21) of EFLAGS can be toggled, i.e. CPUID is supported.
+#[cfg(target_arch = "x86")]
+fn eflags_id_settable() -> bool {
+    let (original, toggled): (u32, u32);
+    unsafe {
+        core::arch::asm!(
+            "pushfd",
+            "pop {original}",
+            "mov {toggled}, {original}",
+            "xor {toggled}, 0x200000",
+            "push {toggled}",
+            "popfd",
+            "pushfd",
+            "pop {toggled}",
+            "push {original}",
+            "popfd",
+            original = out(reg) original,
+            toggled = out(reg) toggled,
+        );
+    }
+    (original ^ toggled) & (1 << 21) != 0
+}
+
+/// Query a CPUID leaf and subleaf.
+///
+/// Returns `None` if CPUID is unavailable, the leaf is above the maximum leaf of its range (basic, hypervisor
+/// or extended), the hypervisor range is only available if the hypervisor bit is set.
+pub(crate) fn cpuid(leaf: u32, subleaf: u32) -> Option<CpuidResult> {
+    if !cpuid_available() {
+        return None;
+    }
+
+    let base = match leaf {
+        0x0000_0000..=0x3FFF_FFFF => 0,
+        0x4000_0000..=0x4FFF_FFFF if __cpuid(1).ecx & (1 << 31) != 0 => 0x4000_0000,
+        0x8000_0000..=0x8FFF_FFFF => 0x8000_0000,
+        _ => return None,
+    };
+    if leaf > __cpuid(base).eax {
+        return None;
+    }
+
+    let result = __cpuid_count(leaf, subleaf);
+    trace_leaf(leaf, subleaf, &result);
+    Some(result)
+}
+
+/// Translation lookaside buffers of a core.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Tlbs {
+    pub l1_data: PhysicalDeviceTlbProperties,
+    pub l1_instruction: PhysicalDeviceTlbProperties,
+    pub l2: PhysicalDeviceTlbProperties,
+}
+
+/// Cache decoded from one of the CPUID cache leaves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CpuidCache {
+    pub level: u32,
+    pub kind: CacheKind,
+    pub properties: PhysicalDeviceCacheProperties,
+}
+
+/// Decode the caches of the leaves `4`, `0x8000001D` (same layout), `0x80000005`
+/// and `0x80000006`.
+///
+/// Other leaves and the terminating subleaves contain no caches. Implausible caches
+/// are reported as absent.
+pub(crate) fn parse_caches(leaf: u32, subleaf: u32, result: &CpuidResult) -> Vec<CpuidCache> {
+    trace_leaf(leaf, subleaf, result);
+    match leaf {
+        4 | 0x8000001D => leaf4_cache(result).into_iter().collect(),
+        0x80000005 => vec![
+            amd_l1_cache(result.ecx, CacheKind::Data),
+            amd_l1_cache(result.edx, CacheKind::Instruction),
+        ],
+        0x80000006 => vec![
+            CpuidCache {
+                level: 2,
+                kind: CacheKind::Unified,
+                properties: amd_l2_cache(result.ecx).sanitized(2),
+            },
+            CpuidCache {
+                level: 3,
+                kind: CacheKind::Unified,
+                properties: amd_l3_cache(result.edx).sanitized(3),
+            },
+        ],
+        _ => Vec::new(),
+    }
+}
+
+/// Decode the 4 KiB page TLBs of the leaves `0x18`, `0x80000005` and `0x80000006`.
+///
+/// Other leaves and subleaves without a 4 KiB page translation contain no TLBs.
+pub(crate) fn parse_tlbs(
+    leaf: u32,
+    subleaf: u32,
+    result: &CpuidResult,
+) -> Vec<(TlbKind, PhysicalDeviceTlbProperties)> {
+    trace_leaf(leaf, subleaf, result);
+    match leaf {
+        0x18 => {
+            let ty = result.edx & 0x1F;
+            let level = (result.edx >> 5) & 0x7;
+            if ty == 0 || result.ebx & 0x1 == 0 {
+                return Vec::new();
+            }
+
+            let kind = match (level, ty) {
+                (1, 1) | (1, 4) => TlbKind::Data,
+                (1, 2) => TlbKind::Instruction,
+                (2, _) => TlbKind::Shared,
+                _ => return Vec::new(),
+            };
+            let ways = result.ebx >> 16;
+            vec![(kind, tlb(ways.saturating_mul(result.ecx), PAGE_4K))]
+        }
+        0x80000005 => vec![
+            (TlbKind::Data, tlb((result.ebx >> 16) & 0xFF, PAGE_4K)),
+            (TlbKind::Instruction, tlb(result.ebx & 0xFF, PAGE_4K)),
+        ],
+        0x80000006 => vec![(TlbKind::Shared, tlb((result.ebx >> 16) & 0xFFF, PAGE_4K))],
+        _ => Vec::new(),
+    }
+}
+
+/// Decode a cache of the deterministic cache parameters leaf `4` or `0x8000001D`.
+///
+/// Returns `None` for the terminating null cache. Types other than data and
+/// instruction are treated as unified.
+fn leaf4_cache(result: &CpuidResult) -> Option<CpuidCache> {
+    let kind = match result.eax & 0x1F {
+        0 => return None,
+        1 => CacheKind::Data,
+        2 => CacheKind::Instruction,
+        _ => CacheKind::Unified,
+    };
+    let level = (result.eax >> 5) & 0x7;
+    let line_size = (result.ebx & 0xFFF) + 1;
+    let partitions = ((result.ebx >> 12) & 0x3FF) + 1;
+    let associativity = ((result.ebx >> 22) & 0x3FF) + 1;
+    let num_sets = result.ecx.saturating_add(1);
+    let size = line_size as u64 * partitions as u64 * associativity as u64 * num_sets as u64;
+
+    // Inclusivity only applies to caches with lower levels. A clear bit means non-inclusive,
+    // which doesn't imply exclusive.
+    let inclusivity = match (level, result.edx & 0x2 != 0) {
+        (1, _) | (_, false) => CacheInclusivity::Unknown,
+        (_, true) => CacheInclusivity::Inclusive,
+    };
+
+    let properties = PhysicalDeviceCacheProperties {
+        size: size.min(u32::MAX as u64) as u32,
+        line_size,
+        associativity,
+        sets: num_sets,
+        inclusivity,
+        write_policy: CacheWritePolicy::Unknown,
+        // Maximum number of addressable logical core IDs, an upper bound of the sharing cores.
+        shared_by_cores: ((result.eax >> 14) & 0xFFF) + 1,
+    };
+    Some(CpuidCache {
+        level,
+        kind,
+        properties: properties.sanitized(level),
+    })
+}
+
+/// Decode a L1 cache register of the AMD leaf `0x80000005`.
+fn amd_l1_cache(register: u32, kind: CacheKind) -> CpuidCache {
+    let size = (register >> 24) * 1024;
+    let line_size = register & 0xFF;
+    // L1 associativity is stored verbatim, `0xFF` denotes fully associative.
+    let associativity = match (register >> 16) & 0xFF {
+        0xFF if line_size > 0 => size / line_size,
+        ways => ways,
+    };
+
+    CpuidCache {
+        level: 1,
+        kind,
+        properties: PhysicalDeviceCacheProperties::from_geometry(size, line_size, associativity)
+            .sanitized(1),
+    }
+}
+
+/// Decode the L2 cache from the ECX register of the AMD leaf `0x80000006`.
+fn amd_l2_cache(ecx: u32) -> PhysicalDeviceCacheProperties {
+    let size = (ecx >> 16) * 1024;
+    let line_size = ecx & 0xFF;
+    let associativity = amd_l2_associativity(size as u64, line_size, (ecx >> 12) & 0xF);
+    PhysicalDeviceCacheProperties::from_geometry(size, line_size, associativity)
+}
+
+/// Decode the L3 cache from the EDX register of the AMD leaf `0x80000006`.
+///
+/// The size is stored in 512 KiB units, the sets are derived from the exact size
+/// while the byte size saturates at `u32::MAX`.
+fn amd_l3_cache(edx: u32) -> PhysicalDeviceCacheProperties {
+    let size = (edx >> 18) as u64 * 512 * 1024;
+    let line_size = edx & 0xFF;
+    let associativity = amd_l2_associativity(size, line_size, (edx >> 12) & 0xF);
+    let sets = size
+        .checked_div(line_size as u64 * associativity as u64)
+        .unwrap_or(0);
+
+    PhysicalDeviceCacheProperties {
+        size: size.min(u32::MAX as u64) as u32,
+        line_size,
+        associativity,
+        sets: sets.min(u32::MAX as u64) as u32,
+        inclusivity: CacheInclusivity::Unknown,
+        write_policy: CacheWritePolicy::Unknown,
+        shared_by_cores: 0,
+    }
+}
+
+/// Decode the encoded L2/L3 associativity of the AMD leaf `0x80000006`.
+fn amd_l2_associativity(size: u64, line_size: u32, ways: u32) -> u32 {
+    match ways {
+        0x1 | 0x2 | 0x4 => ways,
+        0x3 => 3,
+        0x5 => 6,
+        0x6 => 8,
+        0x8 => 16,
+        0xA => 32,
+        0xB => 48,
+        0xC => 64,
+        0xD => 96,
+        0xE => 128,
+        0xF if line_size > 0 => (size / line_size as u64).min(u32::MAX as u64) as u32,
+        _ => 0,
+    }
+}
+
+/// Read the 4 KiB page TLBs from the AMD extended leaves `0x80000005` and `0x80000006`.
+pub(crate) fn amd_tlbs() -> Tlbs {
+    let max_leaf = __cpuid(0x80000000).eax;
+    let mut tlbs = Tlbs::default();
+
+    for leaf in [0x80000005, 0x80000006] {
+        if max_leaf < leaf {
+            continue;
+        }
+        for (kind, properties) in parse_tlbs(leaf, 0, &__cpuid(leaf)) {
+            *tlbs.slot(kind) = properties;
+        }
+    }
+
+    tlbs
+}
+
+/// Read the 4 KiB page TLBs from the deterministic address translation leaf `0x18`,
+/// falling back to the legacy descriptors of leaf `2`.
+pub(crate) fn intel_tlbs() -> Tlbs {
+    let max_leaf = __cpuid(0).eax;
+    let mut tlbs = Tlbs::default();
+
+    if max_leaf >= 0x18 {
+        let max_subleaf = __cpuid_count(0x18, 0).eax;
+        for subleaf in 0..=max_subleaf.min(64) {
+            for (kind, properties) in parse_tlbs(0x18, subleaf, &__cpuid_count(0x18, subleaf)) {
+                *tlbs.slot(kind) = properties;
+            }
+        }
+    }
+
+    if tlbs == Tlbs::default() && max_leaf >= 2 {
+        for descriptor in leaf2_descriptors() {
+            if let Some((kind, properties)) = leaf2_tlb(descriptor) {
+                let slot = tlbs.slot(kind);
+                if slot.entries < properties.entries {
+                    *slot = properties;
+                }
+            }
+        }
+    }
+
+    tlbs
+}
+
+impl Tlbs {
+    fn slot(&mut self, kind: TlbKind) -> &mut PhysicalDeviceTlbProperties {
+        match kind {
+            TlbKind::Data => &mut self.l1_data,
+            TlbKind::Instruction => &mut self.l1_instruction,
+            TlbKind::Shared => &mut self.l2,
+        }
+    }
+}
+
+fn tlb(entries: u32, page_size_bytes: u32) -> PhysicalDeviceTlbProperties {
+    PhysicalDeviceTlbProperties {
+        entries,
+        page_size_bytes: if entries > 0 { page_size_bytes } else { 0 },
+    }
+}
+
+/// Collect the one-byte descriptors of the legacy leaf `2`.
+pub(crate) fn leaf2_descriptors() -> Vec<u8> {
+    cpuid(2, 0).map_or_else(Vec::new, |leaf| parse_descriptors(&leaf))
+}
+
+/// Decode the one-byte descriptors of the legacy leaf `2`.
+fn parse_descriptors(leaf: &CpuidResult) -> Vec<u8> {
+    let mut descriptors = Vec::new();
+
+    for (i, register) in [leaf.eax, leaf.ebx, leaf.ecx, leaf.edx].iter().enumerate() {
+        // Registers with bit 31 set don't contain valid descriptors.
+        if register & (1 << 31) != 0 {
+            continue;
+        }
+        let bytes = register.to_le_bytes();
+        // The lowest byte of EAX is the iteration count.
+        let start = if i == 0 { 1 } else { 0 };
+        descriptors.extend(bytes[start..].iter().copied().filter(|&byte| byte != 0));
+    }
+
+    descriptors
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum TlbKind {
+    Data,
+    Instruction,
+    Shared,
+}
+
+/// Decode 4 KiB page TLB descriptors of leaf `2`.
+fn leaf2_tlb(descriptor: u8) -> Option<(TlbKind, PhysicalDeviceTlbProperties)> {
+    let (kind, entries) = match descriptor {
+        0x01 => (TlbKind::Instruction, 32),
+        0x03 => (TlbKind::Data, 64),
+        0x50 => (TlbKind::Instruction, 64),
+        0x51 => (TlbKind::Instruction, 128),
+        0x52 => (TlbKind::Instruction, 256),
+        0x5B => (TlbKind::Data, 64),
+        0x5C => (TlbKind::Data, 128),
+        0x5D => (TlbKind::Data, 256),
+        0x64 => (TlbKind::Data, 512),
+        0xB0 => (TlbKind::Instruction, 128),
+        0xB2 => (TlbKind::Instruction, 64),
+        0xB3 => (TlbKind::Data, 128),
+        0xB4 => (TlbKind::Data, 256),
+        0xB5 => (TlbKind::Instruction, 64),
+        0xB6 => (TlbKind::Instruction, 128),
+        0xBA => (TlbKind::Data, 64),
+        0xC1 => (TlbKind::Shared, 1024),
+        0xC3 => (TlbKind::Shared, 1536),
+        0xCA => (TlbKind::Shared, 512),
+        _ => return None,
+    };
+
+    Some((kind, tlb(entries, PAGE_4K)))
+}
+
+/// Decode cache descriptors of leaf `2` into level, kind and cache properties.
+pub(crate) fn leaf2_cache(
+    descriptor: u8,
+) -> Option<(u8, CacheKind, PhysicalDeviceCacheProperties)> {
+    use CacheKind::*;
+
+    const K: u32 = 1024;
+    const M: u32 = 1024 * 1024;
+
+    // (level, kind, size, ways, line size)
+    let (level, kind, size, associativity, line_size) = match descriptor {
+        0x06 => (1, Instruction, 8 * K, 4, 32),
+        0x08 => (1, Instruction, 16 * K, 4, 32),
+        0x09 => (1, Instruction, 32 * K, 4, 64),
+        0x0A => (1, Data, 8 * K, 2, 32),
+        0x0C => (1, Data, 16 * K, 4, 32),
+        0x0D => (1, Data, 16 * K, 4, 64),
+        0x0E => (1, Data, 24 * K, 6, 64),
+        0x1D => (2, Unified, 128 * K, 2, 64),
+        0x21 => (2, Unified, 256 * K, 8, 64),
+        0x22 => (3, Unified, 512 * K, 4, 64),
+        0x23 => (3, Unified, M, 8, 64),
+        0x24 => (2, Unified, M, 16, 64),
+        0x25 => (3, Unified, 2 * M, 8, 64),
+        0x29 => (3, Unified, 4 * M, 8, 64),
+        0x2C => (1, Data, 32 * K, 8, 64),
+        0x30 => (1, Instruction, 32 * K, 8, 64),
+        0x41 => (2, Unified, 128 * K, 4, 32),
+        0x42 => (2, Unified, 256 * K, 4, 32),
+        0x43 => (2, Unified, 512 * K, 4, 32),
+        0x44 => (2, Unified, M, 4, 32),
+        0x45 => (2, Unified, 2 * M, 4, 32),
+        0x46 => (3, Unified, 4 * M, 4, 64),
+        0x47 => (3, Unified, 8 * M, 8, 64),
+        0x48 => (2, Unified, 3 * M, 12, 64),
+        0x49 => (2, Unified, 4 * M, 16, 64),
+        0x4A => (3, Unified, 6 * M, 12, 64),
+        0x4B => (3, Unified, 8 * M, 16, 64),
+        0x4C => (3, Unified, 12 * M, 12, 64),
+        0x4D => (3, Unified, 16 * M, 16, 64),
+        0x4E => (2, Unified, 6 * M, 24, 64),
+        0x60 => (1, Data, 16 * K, 8, 64),
+        0x66 => (1, Data, 8 * K, 4, 64),
+        0x67 => (1, Data, 16 * K, 4, 64),
+        0x68 => (1, Data, 32 * K, 4, 64),
+        0x78 => (2, Unified, M, 4, 64),
+        0x79 => (2, Unified, 128 * K, 8, 64),
+        0x7A => (2, Unified, 256 * K, 8, 64),
+        0x7B => (2, Unified, 512 * K, 8, 64),
+        0x7C => (2, Unified, M, 8, 64),
+        0x7D => (2, Unified, 2 * M, 8, 64),
+        0x7F => (2, Unified, 512 * K, 2, 64),
+        0x80 => (2, Unified, 512 * K, 8, 64),
+        0x82 => (2, Unified, 256 * K, 8, 32),
+        0x83 => (2, Unified, 512 * K, 8, 32),
+        0x84 => (2, Unified, M, 8, 32),
+        0x85 => (2, Unified, 2 * M, 8, 32),
+        0x86 => (2, Unified, 512 * K, 4, 64),
+        0x87 => (2, Unified, M, 8, 64),
+        0xD0 => (3, Unified, 512 * K, 4, 64),
+        0xD1 => (3, Unified, M, 4, 64),
+        0xD2 => (3, Unified, 2 * M, 4, 64),
+        0xD6 => (3, Unified, M, 8, 64),
+        0xD7 => (3, Unified, 2 * M, 8, 64),
+        0xD8 => (3, Unified, 4 * M, 8, 64),
+        0xDC => (3, Unified, 1536 * K, 12, 64),
+        0xDD => (3, Unified, 3 * M, 12, 64),
+        0xDE => (3, Unified, 6 * M, 12, 64),
+        0xE2 => (3, Unified, 2 * M, 16, 64),
+        0xE3 => (3, Unified, 4 * M, 16, 64),
+        0xE4 => (3, Unified, 8 * M, 16, 64),
+        0xEA => (3, Unified, 12 * M, 24, 64),
+        0xEB => (3, Unified, 18 * M, 24, 64),
+        0xEC => (3, Unified, 24 * M, 24, 64),
+        _ => return None,
+    };
+
+    Some((
+        level,
+        kind,
+        PhysicalDeviceCacheProperties::from_geometry(size, line_size, associativity),
+    ))
+}
+
+/// Read the SGX, SEV and memory encryption capabilities.
+pub(crate) fn security_features() -> SecurityFeatures {
+    let mut features = SecurityFeatures::default();
+
+    if let Some(leaf7) = cpuid(7, 0) {
+        features.tme = leaf7.ecx & (1 << 13) != 0;
+
+        // Leaf `0x12` is only valid with SGX support, SGX1 is cleared if disabled by firmware.
+        let sgx = leaf7.ebx & (1 << 2) != 0;
+        if let Some(capabilities) = cpuid(0x12, 0).filter(|leaf| sgx && leaf.eax & 0x1 != 0) {
+            let (max_enclave_32bit, max_enclave_64bit) = sgx_max_enclave_bytes(capabilities.edx);
+            features.sgx = true;
+            features.sgx_max_enclave_bytes_32bit = max_enclave_32bit;
+            features.sgx_max_enclave_bytes_64bit = max_enclave_64bit;
+            features.sgx_epc_bytes = (2..64)
+                .map_while(|subleaf| sgx_epc_section_bytes(&cpuid(0x12, subleaf)?))
+                .fold(0, u64::saturating_add);
+        }
+    }
+
+    if let Some(encryption) = cpuid(0x8000001F, 0) {
+        features.tme |= encryption.eax & 0x1 != 0;
+        features.sev = encryption.eax & (1 << 1) != 0;
+    }
+
+    features
+}
+
+/// Decode the maximum enclave sizes (32-bit and 64-bit mode) of the SGX leaf `0x12` EDX.
+fn sgx_max_enclave_bytes(edx: u32) -> (u64, u64) {
+    // Sizes are stored as powers of two.
+    let size = |exponent: u32| match exponent {
+        0 => 0,
+        exponent => 1u64.checked_shl(exponent).unwrap_or(u64::MAX),
+    };
+    (size(edx & 0xFF), size((edx >> 8) & 0xFF))
+}
+
+/// Decode the size of an EPC section of the SGX leaf `0x12` subleaves `2..`.
+///
+/// Returns `None` for the terminating invalid section.
+fn sgx_epc_section_bytes(result: &CpuidResult) -> Option<u64> {
+    if result.eax & 0xF != 0x1 {
+        return None;
+    }
+    Some((result.ecx & 0xFFFF_F000) as u64 | ((result.edx & 0xF_FFFF) as u64) << 32)
+}
+
+/// Read the architectural performance monitoring capabilities of leaf `0xA`.
+pub(crate) fn pmu_info() -> PmuInfo {
+    cpuid(0xA, 0).map_or_else(PmuInfo::default, |result| parse_pmu(&result))
+}
+
+/// Decode the architectural performance monitoring leaf `0xA`.
+fn parse_pmu(result: &CpuidResult) -> PmuInfo {
+    let version = (result.eax & 0xFF) as u8;
+    if version == 0 {
+        return PmuInfo::default();
+    }
+
+    // The fixed counters are only enumerated since version 2.
+    let (fixed_counters, fixed_counter_bits) = if version > 1 {
+        ((result.edx & 0x1F) as u8, ((result.edx >> 5) & 0xFF) as u8)
+    } else {
+        (0, 0)
+    };
+    PmuInfo {
+        version,
+        general_purpose_counters: ((result.eax >> 8) & 0xFF) as u8,
+        general_purpose_counter_bits: ((result.eax >> 16) & 0xFF) as u8,
+        fixed_counters,
+        fixed_counter_bits,
+    }
+}
+
+/// Deterministic xorshift sequence of register values, biased towards the extremes.
+#[cfg(test)]
+pub(crate) fn random_registers(count: usize) -> impl Iterator<Item = CpuidResult> {
+    let mut state = 0x2545_F491_4F6C_DD1Du64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        match state % 8 {
+            0 => 0,
+            1 => u32::MAX,
+            _ => (state >> 32) as u32,
+        }
+    };
+    (0..count).map(move |_| CpuidResult {
+        eax: next(),
+        ebx: next(),
+        ecx: next(),
+        edx: next(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaf4_cache() {
+        // 48 KiB 12-way L1 data cache with 64 byte lines.
+        let result = CpuidResult {
+            eax: 0x0000_4121,
+            ebx: 0x02c0_003f,
+            ecx: 0x0000_003f,
+            edx: 0,
+        };
+        let cache = parse_caches(4, 0, &result).pop().unwrap();
+        assert_eq!((cache.level, cache.kind), (1, CacheKind::Data));
+        assert_eq!(cache.properties.size, 48 * 1024);
+        assert_eq!(cache.properties.line_size, 64);
+        assert_eq!(cache.properties.shared_by_cores, 2);
+
+        let result = CpuidResult {
+            eax: 0xFFFF_FF21,
+            ebx: 0xFFFF_FFFF,
+            ecx: 0xFFFF_FFFF,
+            edx: 0xFFFF_FFFF,
+        };
+        let cache = parse_caches(4, 1, &result).pop().unwrap();
+        assert_eq!(cache.level, 1);
+        assert_eq!(cache.properties, PhysicalDeviceCacheProperties::default());
+
+        // Garbage L3 beyond the 2 GiB bound.
+        let result = CpuidResult {
+            eax: 0xFFFF_FF63,
+            ..result
+        };
+        let cache = parse_caches(4, 3, &result).pop().unwrap();
+        assert_eq!(cache.level, 3);
+        assert!(!cache.properties.is_present());
+
+        // Non-inclusive 2 MiB 16-way L2.
+        let result = CpuidResult {
+            eax: 0x0000_4143,
+            ebx: 0x03c0_003f,
+            ecx: 0x0000_07ff,
+            edx: 0,
+        };
+        let cache = parse_caches(4, 2, &result).pop().unwrap();
+        assert_eq!((cache.level, cache.kind), (2, CacheKind::Unified));
+        assert_eq!(cache.properties.size, 2 << 20);
+        assert_eq!(cache.properties.inclusivity, CacheInclusivity::Unknown);
+
+        let result = CpuidResult { edx: 0x2, ..result };
+        let cache = parse_caches(4, 2, &result).pop().unwrap();
+        assert_eq!(cache.properties.inclusivity, CacheInclusivity::Inclusive);
+
+        // Null cache terminates the subleaves.
+        let result = CpuidResult {
+            eax: 0,
+            ebx: 0,
+            ecx: 0,
+            edx: 0,
+        };
+        assert!(parse_caches(4, 2, &result).is_empty());
+    }
+
+    #[test]
+    fn amd_l3_size() {
+        // 32 MiB 16-way with 64 byte lines.
+        let cache = amd_l3_cache(0x0100_8040);
+        assert_eq!(cache.size, 32 << 20);
+        assert_eq!(cache.sets, 32768);
+        assert_eq!(cache.size_kib(), 32 * 1024);
+
+        // Largest encodable size of 16383 * 512 KiB saturates.
+        let cache = amd_l3_cache(0xFFFC_8040);
+        assert_eq!(cache.size, u32::MAX);
+        assert_eq!(cache.size_kib(), 16383 * 512);
+
+        // It exceeds the L3 bound, so detection discards it.
+        let result = CpuidResult {
+            eax: 0,
+            ebx: 0,
+            ecx: 0,
+            edx: 0xFFFC_8040,
+        };
+        let cache = parse_caches(0x80000006, 0, &result).pop().unwrap();
+        assert!(!cache.properties.is_present());
+    }
+
+    #[test]
+    fn leaf18_tlb() {
+        // 64 entry 4-way L1 data TLB.
+        let result = CpuidResult {
+            eax: 0,
+            ebx: 0x0004_0001,
+            ecx: 16,
+            edx: 0x0000_0021,
+        };
+        assert_eq!(
+            parse_tlbs(0x18, 1, &result),
+            [(TlbKind::Data, tlb(64, PAGE_4K))]
+        );
+
+        let result = CpuidResult {
+            ebx: u32::MAX,
+            ecx: u32::MAX,
+            ..result
+        };
+        assert_eq!(
+            parse_tlbs(0x18, 1, &result),
+            [(TlbKind::Data, tlb(u32::MAX, PAGE_4K))]
+        );
+    }
+
+    #[test]
+    fn sgx_leaves() {
+        assert_eq!(sgx_max_enclave_bytes(0x0000_241F), (1 << 31, 1 << 36));
+        assert_eq!(sgx_max_enclave_bytes(0), (0, 0));
+        assert_eq!(sgx_max_enclave_bytes(0xFFFF), (u64::MAX, u64::MAX));
+
+        // 93.5 MiB section above 4 GiB.
+        let section = CpuidResult {
+            eax: 0x7000_0001,
+            ebx: 0x0000_0001,
+            ecx: 0x05D8_0001,
+            edx: 0,
+        };
+        assert_eq!(sgx_epc_section_bytes(&section), Some(0x05D8_0000));
+        let section = CpuidResult { eax: 0, ..section };
+        assert_eq!(sgx_epc_section_bytes(&section), None);
+    }
+
+    #[test]
+    fn pmu_leaf() {
+        // Alder Lake P-core: version 5, 8 general-purpose and 3 fixed 48-bit counters.
+        let result = CpuidResult {
+            eax: 0x0730_0805,
+            ebx: 0,
+            ecx: 0,
+            edx: 0x0000_8603,
+        };
+        assert_eq!(
+            parse_pmu(&result),
+            PmuInfo {
+                version: 5,
+                general_purpose_counters: 8,
+                general_purpose_counter_bits: 48,
+                fixed_counters: 3,
+                fixed_counter_bits: 48,
+            }
+        );
+
+        // Version 1 doesn't enumerate fixed counters.
+        let result = CpuidResult {
+            eax: 0x0028_0401,
+            edx: u32::MAX,
+            ..result
+        };
+        assert_eq!(parse_pmu(&result).fixed_counters, 0);
+        assert_eq!(parse_pmu(&result).general_purpose_counters, 4);
+
+        let result = CpuidResult { eax: 0, ..result };
+        assert_eq!(parse_pmu(&result), PmuInfo::default());
+    }
+
+    #[test]
+    fn random_leaves() {
+        const LEAVES: [u32; 6] = [2, 4, 0x18, 0x80000005, 0x80000006, 0x8000001D];
+
+        for (i, result) in random_registers(20_000).enumerate() {
+            let leaf = LEAVES[i % LEAVES.len()];
+            let subleaf = (i / LEAVES.len()) as u32;
+
+            for cache in parse_caches(leaf, subleaf, &result) {
+                let properties = &cache.properties;
+                assert!(cache.level <= 7);
+                if properties.is_present() {
+                    let max_size = match cache.level {
+                        1 => 1 << 20,
+                        2 => 256 << 20,
+                        _ => 2 << 30,
+                    };
+                    assert!(properties.size <= max_size, "{:x?}", result);
+                    assert!(properties.line_size.is_power_of_two());
+                    assert!((16..=256).contains(&properties.line_size));
+                }
+            }
+
+            for (_, properties) in parse_tlbs(leaf, subleaf, &result) {
+                let expected_page_size = if properties.entries > 0 { PAGE_4K } else { 0 };
+                assert_eq!(properties.page_size_bytes, expected_page_size);
+            }
+
+            for descriptor in parse_descriptors(&result) {
+                assert_ne!(descriptor, 0);
+                if let Some((level, _, properties)) = leaf2_cache(descriptor) {
+                    assert!((1..=3).contains(&level));
+                    assert!(properties.is_present());
+                }
+                leaf2_tlb(descriptor);
+            }
+
+            sgx_max_enclave_bytes(result.edx);
+            sgx_epc_section_bytes(&result);
+        }
+    }
+
+    #[test]
+    fn leaf2_tlb_descriptors() {
+        assert_eq!(
+            leaf2_tlb(0xB6),
+            Some((TlbKind::Instruction, tlb(128, PAGE_4K)))
+        );
+        assert_eq!(leaf2_tlb(0xCA), Some((TlbKind::Shared, tlb(512, PAGE_4K))));
+        // Cache descriptor
+        assert_eq!(leaf2_tlb(0x2C), None);
+    }
+
+    #[test]
+    fn leaf2_cache_descriptors() {
+        let (level, kind, cache) = leaf2_cache(0x2C).unwrap();
+        assert_eq!((level, kind), (1, CacheKind::Data));
+        assert_eq!(
+            (cache.size, cache.line_size, cache.associativity, cache.sets),
+            (32 * 1024, 64, 8, 64)
+        );
+
+        let (level, _, cache) = leaf2_cache(0xEC).unwrap();
+        assert_eq!((level, cache.size), (3, 24 * 1024 * 1024));
+
+        // TLB descriptor
+        assert!(leaf2_cache(0xB6).is_none());
+        // Use leaf 4
+        assert!(leaf2_cache(0xFF).is_none());
+    }
+}
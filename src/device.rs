@@ -1,22 +1,228 @@
-use crate::properties::PhysicalDeviceProperties;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+use crate::cpuid::arch::CpuidResult;
+use crate::error::DeviceError;
+use crate::features::PhysicalDeviceFeatures;
+use crate::properties::{ByteSize, DetectionBackend, PhysicalDeviceProperties};
+use std::sync::OnceLock;
 
-///
+/// Physical Device
 pub struct PhysicalDevice {
     properties: PhysicalDeviceProperties,
+    features: PhysicalDeviceFeatures,
 }
 
 impl PhysicalDevice {
     /// Enumerate all available physical devices.
     ///
-    /// Currently, this will only return the default CPU adapter.
+    /// Currently, this will only return the default CPU adapter, restricted to the
+    /// cores the current process is allowed to use (see `system_constrained`).
+    /// Use `enumerate_all` to query each physical package separately.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the device couldn't be queried, see `try_enumerate`.
     pub fn enumerate() -> Self {
-        let properties = PhysicalDeviceProperties::system();
+        Self::try_enumerate().unwrap()
+    }
+
+    /// Enumerate one physical device per physical package (socket).
+    ///
+    /// Single socket systems return a single device, equal to `enumerate`.
+    pub fn enumerate_all() -> Vec<PhysicalDevice> {
+        Self::try_enumerate_all().unwrap()
+    }
+
+    /// Enumerate one physical device per physical package (socket).
+    ///
+    /// Same as `enumerate_all`, but returns an error instead of panicking
+    /// if the devices couldn't be queried.
+    pub fn try_enumerate_all() -> Result<Vec<PhysicalDevice>, DeviceError> {
+        let features = PhysicalDeviceFeatures::system();
+
+        let packages = PhysicalDeviceProperties::try_system_packages()?;
+        #[cfg(feature = "env-override")]
+        let packages = packages
+            .into_iter()
+            .map(crate::overrides::apply)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(packages
+            .into_iter()
+            .map(|properties| PhysicalDevice {
+                properties,
+                features: features.clone(),
+            })
+            .collect())
+    }
+
+    /// Get the default physical device, enumerated once on first use.
+    ///
+    /// Subsequent calls return the same shared instance,
+    /// use `enumerate` to query the current state of the device.
+    pub fn cached() -> &'static PhysicalDevice {
+        static DEVICE: OnceLock<PhysicalDevice> = OnceLock::new();
+        DEVICE.get_or_init(PhysicalDevice::enumerate)
+    }
+
+    /// Enumerate all available physical devices.
+    ///
+    /// Same as `enumerate`, but returns an error instead of panicking
+    /// if the device couldn't be queried.
+    pub fn try_enumerate() -> Result<Self, DeviceError> {
+        Self::try_enumerate_with(DetectionBackend::Auto)
+    }
+
+    /// Enumerate the default physical device without blocking the calling thread.
+    ///
+    /// The property detection, including CPUID, runs on a dedicated thread as the per core
+    /// CPUID reads pin the thread to each logical core. Only the feature detection runs
+    /// inline. Works with any async runtime, no `tokio` integration is provided.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the device couldn't be queried, see `try_enumerate_async`.
+    #[cfg(feature = "async")]
+    pub async fn enumerate_async() -> Self {
+        Self::try_enumerate_async().await.unwrap()
+    }
+
+    /// Enumerate the default physical device without blocking the calling thread.
+    ///
+    /// Same as `enumerate_async`, but returns an error instead of panicking
+    /// if the device couldn't be queried.
+    #[cfg(feature = "async")]
+    pub async fn try_enumerate_async() -> Result<Self, DeviceError> {
+        let features = PhysicalDeviceFeatures::system();
+        let properties = crate::blocking::spawn(|| {
+            let properties =
+                PhysicalDeviceProperties::try_system_constrained_with(DetectionBackend::Auto)?;
+            #[cfg(feature = "env-override")]
+            let properties = crate::overrides::apply(properties)?;
+            Ok::<_, DeviceError>(properties)
+        })
+        .await?;
+
+        Ok(PhysicalDevice {
+            properties,
+            features,
+        })
+    }
+
+    /// Enumerate the default physical device with the given detection backend.
+    ///
+    /// `enumerate` is equivalent to `DetectionBackend::Auto`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the device couldn't be queried, see `try_enumerate_with`.
+    pub fn enumerate_with(backend: DetectionBackend) -> Self {
+        Self::try_enumerate_with(backend).unwrap()
+    }
+
+    /// Enumerate the default physical device with the given detection backend.
+    ///
+    /// Returns `UnsupportedBackend` if the backend isn't available on the system.
+    pub fn try_enumerate_with(backend: DetectionBackend) -> Result<Self, DeviceError> {
+        let properties = PhysicalDeviceProperties::try_system_constrained_with(backend)?;
+        #[cfg(feature = "env-override")]
+        let properties = crate::overrides::apply(properties)?;
+        let features = PhysicalDeviceFeatures::system();
 
-        PhysicalDevice { properties }
+        Ok(PhysicalDevice {
+            properties,
+            features,
+        })
     }
 
+    /// Create a synthetic physical device from the given properties, e.g. for testing.
     ///
+    /// All features are reported as unsupported.
+    pub fn from_properties(properties: PhysicalDeviceProperties) -> Self {
+        PhysicalDevice {
+            properties,
+            features: PhysicalDeviceFeatures::default(),
+        }
+    }
+
+    /// Get the properties of the physical device.
     pub fn properties(&self) -> &PhysicalDeviceProperties {
         &self.properties
     }
+
+    /// Get mutable access to the properties of the physical device, e.g. for testing.
+    pub fn properties_mut(&mut self) -> &mut PhysicalDeviceProperties {
+        &mut self.properties
+    }
+
+    /// Re-query the volatile properties: available memory and current frequency.
+    ///
+    /// Cheaper than `enumerate`, as the topology isn't enumerated again.
+    pub fn refresh(&mut self) {
+        self.properties.refresh();
+    }
+
+    /// Query a raw CPUID leaf and subleaf of the current system.
+    ///
+    /// Returns `None` if the leaf isn't supported by the device.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    pub fn cpuid(&self, leaf: u32, subleaf: u32) -> Option<CpuidResult> {
+        crate::cpuid::cpuid(leaf, subleaf)
+    }
+
+    /// Get the supported features of the physical device.
+    pub fn features(&self) -> &PhysicalDeviceFeatures {
+        &self.features
+    }
+
+    /// Returns if the current process can change the thread affinity.
+    ///
+    /// Probed by setting the current affinity mask of the thread again, which may fail
+    /// in sandboxes. Always `false` on platforms other than Linux and Windows.
+    pub fn affinity_supported(&self) -> bool {
+        crate::affinity::affinity_supported()
+    }
+
+    /// Returns if both devices describe the same hardware.
+    ///
+    /// Compares all properties and features except the volatile ones updated by
+    /// `refresh` (available memory, current frequency) and the turbo setting.
+    pub fn is_same_hardware(&self, other: &PhysicalDevice) -> bool {
+        let stable = |properties: &PhysicalDeviceProperties| PhysicalDeviceProperties {
+            available_memory_bytes: 0,
+            current_frequency_mhz: 0,
+            turbo_enabled: None,
+            ..properties.clone()
+        };
+
+        self.features == other.features && stable(&self.properties) == stable(&other.properties)
+    }
+
+    /// Compact single line identifier, e.g. `AMD Ryzen 9 5950X (16c/32t, L3 64 MiB)`.
+    ///
+    /// Brand strings longer than 48 characters are truncated, the vendor is used
+    /// if the brand string is unknown.
+    pub fn summary_line(&self) -> String {
+        const MAX_BRAND_CHARS: usize = 48;
+
+        let properties = &self.properties;
+        let device = properties.device.trim();
+        let mut summary = if device.is_empty() {
+            properties.vendor.to_string()
+        } else if device.chars().count() > MAX_BRAND_CHARS {
+            let truncated = device.chars().take(MAX_BRAND_CHARS - 1).collect::<String>();
+            format!("{}…", truncated.trim_end())
+        } else {
+            String::from(device)
+        };
+
+        summary.push_str(&format!(
+            " ({}c/{}t",
+            properties.physical_cores, properties.logical_cores
+        ));
+        if properties.l3_cache.is_present() {
+            summary.push_str(&format!(", L3 {}", ByteSize(properties.l3_cache.size as _)));
+        }
+        summary.push(')');
+        summary
+    }
 }
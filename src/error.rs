@@ -0,0 +1,36 @@
+use alloc::string::String;
+use core::fmt;
+
+/// Errors which may occur while querying the physical device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceError {
+    /// A system call failed with the contained OS error code.
+    SyscallFailed(i32),
+    /// The system returned data in an unexpected layout.
+    UnexpectedLayout,
+    /// The target architecture is not supported.
+    UnsupportedArchitecture,
+    /// The property overrides of the environment are invalid.
+    InvalidOverride(String),
+    /// The requested detection backend is not available on the system.
+    UnsupportedBackend,
+}
+
+impl fmt::Display for DeviceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeviceError::SyscallFailed(code) => write!(f, "system call failed (os error {})", code),
+            DeviceError::UnexpectedLayout => {
+                write!(f, "system returned data in an unexpected layout")
+            }
+            DeviceError::UnsupportedArchitecture => write!(f, "unsupported architecture"),
+            DeviceError::UnsupportedBackend => write!(f, "unsupported detection backend"),
+            DeviceError::InvalidOverride(reason) => {
+                write!(f, "invalid property override: {}", reason)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DeviceError {}
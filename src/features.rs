@@ -0,0 +1,437 @@
+#[cfg(feature = "std")]
+use alloc::{vec, vec::Vec};
+
+/// Physical Device Features
+///
+/// Instruction set extensions supported by the device.
+/// Extensions which require OS support for saving their register state
+/// (e.g. AVX) are only reported if the OS has enabled it.
+/// Under binary translation (see `PhysicalDeviceProperties::translation`) the
+/// features of the emulated CPU are reported.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhysicalDeviceFeatures {
+    /// SSE support.
+    pub sse: bool,
+    /// SSE2 support.
+    pub sse2: bool,
+    /// x87 floating point unit support.
+    pub fpu: bool,
+    /// CMOV (conditional move) instruction support.
+    pub cmov: bool,
+    /// CMPXCHG8B instruction support.
+    pub cx8: bool,
+    /// MMX support.
+    pub mmx: bool,
+    /// FXSAVE and FXRSTOR instruction support.
+    pub fxsr: bool,
+    /// SYSCALL and SYSRET instruction support.
+    pub syscall: bool,
+    /// SSE3 support.
+    pub sse3: bool,
+    /// Supplemental SSE3 support.
+    pub ssse3: bool,
+    /// SSE4.1 support.
+    pub sse4_1: bool,
+    /// SSE4.2 support.
+    pub sse4_2: bool,
+    /// POPCNT instruction support.
+    pub popcnt: bool,
+    /// CMPXCHG16B instruction support.
+    pub cmpxchg16b: bool,
+    /// LAHF and SAHF instruction support in 64-bit mode.
+    pub lahf_sahf: bool,
+    /// MOVBE instruction support.
+    pub movbe: bool,
+    /// AVX support.
+    pub avx: bool,
+    /// AVX2 support.
+    pub avx2: bool,
+    /// AVX-512 Foundation support.
+    pub avx512f: bool,
+    /// AVX-512 Vector Length Extensions support.
+    pub avx512vl: bool,
+    /// AVX-512 Byte and Word Instructions support.
+    pub avx512bw: bool,
+    /// AVX-512 Doubleword and Quadword Instructions support.
+    pub avx512dq: bool,
+    /// AVX-512 Conflict Detection Instructions support.
+    pub avx512cd: bool,
+    /// AVX-512 Vector Neural Network Instructions support.
+    pub avx512vnni: bool,
+    /// AVX-512 BFloat16 Instructions support.
+    pub avx512_bf16: bool,
+    /// Fused multiply-add (FMA3) support.
+    pub fma: bool,
+    /// Half precision conversion (F16C) support.
+    pub f16c: bool,
+    /// Bit Manipulation Instruction Set 1 support.
+    pub bmi1: bool,
+    /// Bit Manipulation Instruction Set 2 support.
+    pub bmi2: bool,
+    /// LZCNT instruction support.
+    pub lzcnt: bool,
+    /// RDRAND instruction support.
+    pub rdrand: bool,
+    /// RDSEED instruction support.
+    pub rdseed: bool,
+    /// AES-NI support.
+    pub aes: bool,
+    /// Carry-less multiplication (PCLMULQDQ) support.
+    pub pclmulqdq: bool,
+    /// SHA extensions support.
+    pub sha: bool,
+    /// CLFLUSH instruction support.
+    pub clflush: bool,
+    /// CLFLUSHOPT instruction support.
+    pub clflushopt: bool,
+    /// CLWB (cache line write back) instruction support.
+    pub clwb: bool,
+    /// MONITOR/MWAIT instruction support.
+    ///
+    /// Usually only executable by the kernel, check `waitpkg` for user-level waits.
+    pub monitor: bool,
+    /// User-level wait (UMONITOR, UMWAIT, TPAUSE) support.
+    pub waitpkg: bool,
+    /// NEON support.
+    pub neon: bool,
+    /// Advanced SIMD support (same as NEON on aarch64).
+    pub asimd: bool,
+    /// Scalable Vector Extension support.
+    pub sve: bool,
+    /// Scalable Vector Extension 2 support.
+    pub sve2: bool,
+    /// Advanced SIMD dot product support.
+    pub dotprod: bool,
+    /// Half precision floating point (scalar and Advanced SIMD) support.
+    pub fp16: bool,
+    /// SVE vector length in bits.
+    ///
+    /// `0` if SVE is not supported or the length couldn't be retrieved.
+    pub sve_vector_bits: u32,
+    /// Size in bytes of the line flushed by CLFLUSH.
+    ///
+    /// `0` if CLFLUSH is not supported.
+    pub clflush_line_size: u32,
+    /// Smallest size in bytes of the range monitored by MONITOR.
+    ///
+    /// `0` if MONITOR is not supported or the size couldn't be retrieved.
+    pub monitor_line_size_min: u32,
+    /// Largest size in bytes of the range monitored by MONITOR.
+    ///
+    /// `0` if MONITOR is not supported or the size couldn't be retrieved.
+    pub monitor_line_size_max: u32,
+}
+
+impl PhysicalDeviceFeatures {
+    /// Names and support of all boolean features.
+    #[cfg(feature = "std")]
+    pub(crate) fn flags(&self) -> Vec<(&'static str, bool)> {
+        vec![
+            ("sse", self.sse),
+            ("sse2", self.sse2),
+            ("fpu", self.fpu),
+            ("cmov", self.cmov),
+            ("cx8", self.cx8),
+            ("mmx", self.mmx),
+            ("fxsr", self.fxsr),
+            ("syscall", self.syscall),
+            ("sse3", self.sse3),
+            ("ssse3", self.ssse3),
+            ("sse4.1", self.sse4_1),
+            ("sse4.2", self.sse4_2),
+            ("popcnt", self.popcnt),
+            ("cmpxchg16b", self.cmpxchg16b),
+            ("lahf_sahf", self.lahf_sahf),
+            ("movbe", self.movbe),
+            ("avx", self.avx),
+            ("avx2", self.avx2),
+            ("avx512f", self.avx512f),
+            ("avx512vl", self.avx512vl),
+            ("avx512bw", self.avx512bw),
+            ("avx512dq", self.avx512dq),
+            ("avx512cd", self.avx512cd),
+            ("avx512vnni", self.avx512vnni),
+            ("avx512bf16", self.avx512_bf16),
+            ("fma", self.fma),
+            ("f16c", self.f16c),
+            ("bmi1", self.bmi1),
+            ("bmi2", self.bmi2),
+            ("lzcnt", self.lzcnt),
+            ("rdrand", self.rdrand),
+            ("rdseed", self.rdseed),
+            ("aes", self.aes),
+            ("pclmulqdq", self.pclmulqdq),
+            ("sha", self.sha),
+            ("clflush", self.clflush),
+            ("clflushopt", self.clflushopt),
+            ("clwb", self.clwb),
+            ("monitor", self.monitor),
+            ("waitpkg", self.waitpkg),
+            ("neon", self.neon),
+            ("asimd", self.asimd),
+            ("sve", self.sve),
+            ("sve2", self.sve2),
+            ("dotprod", self.dotprod),
+            ("fp16", self.fp16),
+        ]
+    }
+
+    /// Width of the widest usable vector registers in bits.
+    ///
+    /// Only considers extensions whose register state is saved by the OS.
+    /// On aarch64 the SVE vector length is used if known, `128` for NEON.
+    /// `0` if no vector extension is supported.
+    pub fn max_vector_bits(&self) -> u32 {
+        if self.avx512f {
+            512
+        } else if self.avx || self.avx2 {
+            256
+        } else if self.sve && self.sve_vector_bits > 0 {
+            self.sve_vector_bits
+        } else if self.sse || self.sse2 || self.neon || self.asimd || self.sve {
+            128
+        } else {
+            0
+        }
+    }
+
+    /// x86-64 microarchitecture level (`1` to `4`) as defined by the x86-64 psABI.
+    ///
+    /// * v1: CMOV, CX8, FPU, FXSR, MMX, SCE (SYSCALL), SSE, SSE2
+    /// * v2: CMPXCHG16B, LAHF/SAHF, POPCNT, SSE3, SSSE3, SSE4.1, SSE4.2
+    /// * v3: AVX, AVX2, BMI1, BMI2, F16C, FMA, LZCNT, MOVBE
+    /// * v4: AVX-512 F, BW, CD, DQ, VL
+    ///
+    /// `0` for non-x86 devices.
+    pub fn x86_64_level(&self) -> u8 {
+        let v1 = self.cmov
+            && self.cx8
+            && self.fpu
+            && self.fxsr
+            && self.mmx
+            && self.syscall
+            && self.sse
+            && self.sse2;
+        let v2 = v1
+            && self.cmpxchg16b
+            && self.lahf_sahf
+            && self.popcnt
+            && self.sse3
+            && self.ssse3
+            && self.sse4_1
+            && self.sse4_2;
+        let v3 = v2
+            && self.avx
+            && self.avx2
+            && self.bmi1
+            && self.bmi2
+            && self.f16c
+            && self.fma
+            && self.lzcnt
+            && self.movbe;
+        let v4 =
+            v3 && self.avx512f && self.avx512bw && self.avx512cd && self.avx512dq && self.avx512vl;
+
+        [v1, v2, v3, v4].iter().filter(|&&level| level).count() as u8
+    }
+
+    /// Query the features of the current system.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    pub fn system() -> Self {
+        use crate::cpuid::arch::{__cpuid, __cpuid_count, _xgetbv, CpuidResult};
+
+        #[target_feature(enable = "xsave")]
+        unsafe fn xcr0() -> u64 {
+            _xgetbv(0)
+        }
+
+        // Only the baseline of the target is known, x86-64-v1 for x86_64 and SSE2 for i686.
+        if !crate::cpuid::cpuid_available() {
+            let x86_64 = cfg!(target_arch = "x86_64");
+            return PhysicalDeviceFeatures {
+                fpu: x86_64,
+                cmov: x86_64,
+                cx8: x86_64,
+                mmx: x86_64,
+                fxsr: cfg!(target_feature = "fxsr"),
+                syscall: x86_64,
+                sse: cfg!(target_feature = "sse"),
+                sse2: cfg!(target_feature = "sse2"),
+                ..Default::default()
+            };
+        }
+
+        let bit = |v: u32, n: u32| v & (1 << n) != 0;
+
+        let max_leaf = __cpuid(0).eax;
+        let leaf1 = __cpuid_count(1, 0);
+        let empty = CpuidResult {
+            eax: 0,
+            ebx: 0,
+            ecx: 0,
+            edx: 0,
+        };
+        let leaf7 = if max_leaf >= 7 {
+            __cpuid_count(7, 0)
+        } else {
+            empty
+        };
+        let leaf7_1 = if max_leaf >= 7 && leaf7.eax >= 1 {
+            __cpuid_count(7, 1)
+        } else {
+            empty
+        };
+        let monitor = bit(leaf1.ecx, 3);
+        let leaf5 = if monitor && max_leaf >= 5 {
+            __cpuid(5)
+        } else {
+            empty
+        };
+        let extended_leaf1 = if __cpuid(0x80000000).eax >= 0x80000001 {
+            __cpuid(0x80000001)
+        } else {
+            empty
+        };
+
+        // Check if the OS saves the extended register state on context switches.
+        let xcr0 = if bit(leaf1.ecx, 27) {
+            unsafe { xcr0() }
+        } else {
+            0
+        };
+        crate::cpuid::trace_leaf(1, 0, &leaf1);
+        crate::cpuid::trace_leaf(5, 0, &leaf5);
+        crate::cpuid::trace_leaf(7, 0, &leaf7);
+        crate::cpuid::trace_leaf(7, 1, &leaf7_1);
+        crate::cpuid::trace_leaf(0x80000001, 0, &extended_leaf1);
+        trace!(xcr0 = format_args!("{:#x}", xcr0), "xgetbv");
+        let ymm_state = xcr0 & 0x6 == 0x6;
+        let zmm_state = ymm_state && xcr0 & 0xE0 == 0xE0;
+        let clflush = bit(leaf1.edx, 19);
+
+        PhysicalDeviceFeatures {
+            fpu: bit(leaf1.edx, 0),
+            cmov: bit(leaf1.edx, 15),
+            cx8: bit(leaf1.edx, 8),
+            mmx: bit(leaf1.edx, 23),
+            fxsr: bit(leaf1.edx, 24),
+            syscall: bit(extended_leaf1.edx, 11),
+            sse: bit(leaf1.edx, 25),
+            sse2: bit(leaf1.edx, 26),
+            sse3: bit(leaf1.ecx, 0),
+            ssse3: bit(leaf1.ecx, 9),
+            sse4_1: bit(leaf1.ecx, 19),
+            sse4_2: bit(leaf1.ecx, 20),
+            popcnt: bit(leaf1.ecx, 23),
+            cmpxchg16b: bit(leaf1.ecx, 13),
+            lahf_sahf: bit(extended_leaf1.ecx, 0),
+            movbe: bit(leaf1.ecx, 22),
+            avx: ymm_state && bit(leaf1.ecx, 28),
+            avx2: ymm_state && bit(leaf7.ebx, 5),
+            avx512f: zmm_state && bit(leaf7.ebx, 16),
+            avx512vl: zmm_state && bit(leaf7.ebx, 31),
+            avx512bw: zmm_state && bit(leaf7.ebx, 30),
+            avx512dq: zmm_state && bit(leaf7.ebx, 17),
+            avx512cd: zmm_state && bit(leaf7.ebx, 28),
+            avx512vnni: zmm_state && bit(leaf7.ecx, 11),
+            avx512_bf16: zmm_state && bit(leaf7_1.eax, 5),
+            fma: ymm_state && bit(leaf1.ecx, 12),
+            f16c: ymm_state && bit(leaf1.ecx, 29),
+            bmi1: bit(leaf7.ebx, 3),
+            bmi2: bit(leaf7.ebx, 8),
+            lzcnt: bit(extended_leaf1.ecx, 5),
+            rdrand: bit(leaf1.ecx, 30),
+            rdseed: bit(leaf7.ebx, 18),
+            aes: bit(leaf1.ecx, 25),
+            pclmulqdq: bit(leaf1.ecx, 1),
+            sha: bit(leaf7.ebx, 29),
+            clflush,
+            clflushopt: bit(leaf7.ebx, 23),
+            clwb: bit(leaf7.ebx, 24),
+            monitor,
+            waitpkg: bit(leaf7.ecx, 5),
+            // Stored in 8 byte units.
+            clflush_line_size: if clflush {
+                ((leaf1.ebx >> 8) & 0xFF) * 8
+            } else {
+                0
+            },
+            monitor_line_size_min: leaf5.eax & 0xFFFF,
+            monitor_line_size_max: leaf5.ebx & 0xFFFF,
+            ..Default::default()
+        }
+    }
+
+    /// Query the features of the current system.
+    ///
+    /// On Linux the features are read from the auxiliary vector (`AT_HWCAP`, `AT_HWCAP2`),
+    /// on macOS from `hw.optional` sysctls.
+    #[cfg(target_arch = "aarch64")]
+    pub fn system() -> Self {
+        #[cfg(target_os = "linux")]
+        {
+            const HWCAP_ASIMD: libc::c_ulong = 1 << 1;
+            const HWCAP_FPHP: libc::c_ulong = 1 << 9;
+            const HWCAP_ASIMDHP: libc::c_ulong = 1 << 10;
+            const HWCAP_ASIMDDP: libc::c_ulong = 1 << 20;
+            const HWCAP_SVE: libc::c_ulong = 1 << 22;
+            const HWCAP2_SVE2: libc::c_ulong = 1 << 1;
+            const PR_SVE_GET_VL: libc::c_int = 51;
+            const PR_SVE_VL_LEN_MASK: libc::c_int = 0xFFFF;
+
+            let hwcap = unsafe { libc::getauxval(libc::AT_HWCAP) };
+            let hwcap2 = unsafe { libc::getauxval(libc::AT_HWCAP2) };
+            let asimd = hwcap & HWCAP_ASIMD != 0;
+            let sve = hwcap & HWCAP_SVE != 0;
+
+            // The vector length is returned in bytes.
+            let sve_vector_bits = if sve {
+                let vl = unsafe { libc::prctl(PR_SVE_GET_VL) };
+                if vl > 0 {
+                    ((vl & PR_SVE_VL_LEN_MASK) * 8) as u32
+                } else {
+                    0
+                }
+            } else {
+                0
+            };
+
+            PhysicalDeviceFeatures {
+                neon: asimd,
+                asimd,
+                sve,
+                sve2: hwcap2 & HWCAP2_SVE2 != 0,
+                dotprod: hwcap & HWCAP_ASIMDDP != 0,
+                fp16: hwcap & HWCAP_FPHP != 0 && hwcap & HWCAP_ASIMDHP != 0,
+                sve_vector_bits,
+                ..Default::default()
+            }
+        }
+        #[cfg(all(feature = "std", target_os = "macos"))]
+        {
+            let feature = |name: &str| crate::sysctl::read_u64(name).unwrap_or(0) != 0;
+            let asimd = feature("hw.optional.AdvSIMD") || feature("hw.optional.neon");
+
+            PhysicalDeviceFeatures {
+                neon: asimd,
+                asimd,
+                sve: feature("hw.optional.arm.FEAT_SVE"),
+                sve2: feature("hw.optional.arm.FEAT_SVE2"),
+                dotprod: feature("hw.optional.arm.FEAT_DotProd"),
+                fp16: feature("hw.optional.arm.FEAT_FP16"),
+                ..Default::default()
+            }
+        }
+        #[cfg(not(any(target_os = "linux", all(feature = "std", target_os = "macos"))))]
+        {
+            PhysicalDeviceFeatures::default()
+        }
+    }
+
+    /// Query the features of the current system.
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    pub fn system() -> Self {
+        PhysicalDeviceFeatures::default()
+    }
+}
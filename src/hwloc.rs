@@ -0,0 +1,222 @@
+//! Helpers for querying the topology via `libhwloc` (version 2).
+
+use crate::error::DeviceError;
+use crate::properties::{NumaNode, PhysicalDeviceCacheProperties};
+use std::os::raw::{c_int, c_uint, c_void};
+use std::ptr;
+
+#[allow(non_camel_case_types)]
+type hwloc_topology_t = *mut c_void;
+#[allow(non_camel_case_types)]
+type hwloc_bitmap_t = *mut c_void;
+
+const HWLOC_OBJ_CORE: c_int = 2;
+const HWLOC_OBJ_PU: c_int = 3;
+const HWLOC_OBJ_L1CACHE: c_int = 4;
+const HWLOC_OBJ_L2CACHE: c_int = 5;
+const HWLOC_OBJ_L3CACHE: c_int = 6;
+const HWLOC_OBJ_L4CACHE: c_int = 7;
+const HWLOC_OBJ_L1ICACHE: c_int = 9;
+const HWLOC_OBJ_NUMANODE: c_int = 13;
+
+/// Prefix of `struct hwloc_obj` up to the cpuset.
+#[allow(dead_code)]
+#[repr(C)]
+struct HwlocObj {
+    ty: c_int,
+    subtype: *mut c_void,
+    os_index: c_uint,
+    name: *mut c_void,
+    total_memory: u64,
+    attr: *mut HwlocCacheAttr,
+    depth: c_int,
+    logical_index: c_uint,
+    next_cousin: *mut HwlocObj,
+    prev_cousin: *mut HwlocObj,
+    parent: *mut HwlocObj,
+    sibling_rank: c_uint,
+    next_sibling: *mut HwlocObj,
+    prev_sibling: *mut HwlocObj,
+    arity: c_uint,
+    children: *mut *mut HwlocObj,
+    first_child: *mut HwlocObj,
+    last_child: *mut HwlocObj,
+    symmetric_subtree: c_int,
+    memory_arity: c_uint,
+    memory_first_child: *mut HwlocObj,
+    io_arity: c_uint,
+    io_first_child: *mut HwlocObj,
+    misc_arity: c_uint,
+    misc_first_child: *mut HwlocObj,
+    cpuset: hwloc_bitmap_t,
+}
+
+/// `struct hwloc_cache_attr_s`, the cache member of `union hwloc_obj_attr_u`.
+#[allow(dead_code)]
+#[repr(C)]
+struct HwlocCacheAttr {
+    size: u64,
+    depth: c_uint,
+    linesize: c_uint,
+    associativity: c_int,
+    ty: c_int,
+}
+
+#[link(name = "hwloc")]
+extern "C" {
+    fn hwloc_get_api_version() -> c_uint;
+    fn hwloc_topology_init(topology: *mut hwloc_topology_t) -> c_int;
+    fn hwloc_topology_load(topology: hwloc_topology_t) -> c_int;
+    fn hwloc_topology_destroy(topology: hwloc_topology_t);
+    fn hwloc_get_type_depth(topology: hwloc_topology_t, ty: c_int) -> c_int;
+    fn hwloc_get_nbobjs_by_depth(topology: hwloc_topology_t, depth: c_int) -> c_uint;
+    fn hwloc_get_obj_by_depth(
+        topology: hwloc_topology_t,
+        depth: c_int,
+        idx: c_uint,
+    ) -> *mut HwlocObj;
+    fn hwloc_bitmap_first(bitmap: hwloc_bitmap_t) -> c_int;
+    fn hwloc_bitmap_next(bitmap: hwloc_bitmap_t, prev: c_int) -> c_int;
+}
+
+/// Topology of the system as discovered by hwloc.
+#[derive(Debug, Default)]
+pub(crate) struct HwlocTopology {
+    pub logical_cores: usize,
+    pub physical_cores: usize,
+    pub l1_cache_data: PhysicalDeviceCacheProperties,
+    pub l1_cache_instruction: PhysicalDeviceCacheProperties,
+    pub l2_cache: PhysicalDeviceCacheProperties,
+    pub l3_cache: PhysicalDeviceCacheProperties,
+    pub l4_cache: PhysicalDeviceCacheProperties,
+    pub numa_nodes: Vec<NumaNode>,
+}
+
+/// Loaded hwloc topology, destroyed on drop.
+struct Topology(hwloc_topology_t);
+
+impl Drop for Topology {
+    fn drop(&mut self) {
+        unsafe { hwloc_topology_destroy(self.0) }
+    }
+}
+
+impl Topology {
+    /// Objects of the given type.
+    fn objects(&self, ty: c_int) -> Vec<&HwlocObj> {
+        let depth = unsafe { hwloc_get_type_depth(self.0, ty) };
+        // Negative depths other than the virtual NUMA depth denote missing or multiple levels.
+        if depth < 0 && ty != HWLOC_OBJ_NUMANODE {
+            return Vec::new();
+        }
+
+        let count = unsafe { hwloc_get_nbobjs_by_depth(self.0, depth) };
+        (0..count)
+            .filter_map(|idx| unsafe { hwloc_get_obj_by_depth(self.0, depth, idx).as_ref() })
+            .collect()
+    }
+}
+
+/// OS indices of the logical cores of an object.
+fn cpus(obj: &HwlocObj) -> Vec<usize> {
+    let mut cpus = Vec::new();
+    if obj.cpuset.is_null() {
+        return cpus;
+    }
+
+    let mut cpu = unsafe { hwloc_bitmap_first(obj.cpuset) };
+    while cpu >= 0 {
+        cpus.push(cpu as usize);
+        cpu = unsafe { hwloc_bitmap_next(obj.cpuset, cpu) };
+    }
+    cpus
+}
+
+/// Properties of the first cache object of the given type.
+fn cache(topology: &Topology, ty: c_int) -> PhysicalDeviceCacheProperties {
+    let obj = match topology.objects(ty).first() {
+        Some(obj) => *obj,
+        None => return PhysicalDeviceCacheProperties::default(),
+    };
+    let attr = match unsafe { obj.attr.as_ref() } {
+        Some(attr) => attr,
+        None => return PhysicalDeviceCacheProperties::default(),
+    };
+
+    let size = attr.size.min(u32::MAX as u64) as u32;
+    let line_size = attr.linesize;
+    // A negative associativity denotes a fully associative cache.
+    let associativity = match attr.associativity {
+        ways if ways > 0 => ways as u32,
+        ways if ways < 0 && line_size > 0 => size / line_size,
+        _ => 0,
+    };
+
+    PhysicalDeviceCacheProperties {
+        shared_by_cores: cpus(obj).len() as u32,
+        ..PhysicalDeviceCacheProperties::from_geometry(size, line_size, associativity)
+    }
+}
+
+/// Discover the cores, caches and NUMA nodes of the system.
+///
+/// Returns `UnsupportedBackend` if hwloc failed to discover the topology.
+pub(crate) fn topology() -> Result<HwlocTopology, DeviceError> {
+    // The object layout is only stable within a major version.
+    let version = unsafe { hwloc_get_api_version() };
+    debug!(version = format_args!("{:#x}", version), "hwloc");
+    if version >> 16 != 2 {
+        return Err(DeviceError::UnexpectedLayout);
+    }
+
+    let mut raw = ptr::null_mut();
+    if unsafe { hwloc_topology_init(&mut raw) } != 0 {
+        debug!("hwloc_topology_init failed");
+        return Err(DeviceError::UnsupportedBackend);
+    }
+    let topology = Topology(raw);
+    if unsafe { hwloc_topology_load(topology.0) } != 0 {
+        debug!("hwloc_topology_load failed");
+        return Err(DeviceError::UnsupportedBackend);
+    }
+
+    let numa_nodes = topology
+        .objects(HWLOC_OBJ_NUMANODE)
+        .into_iter()
+        .map(|node| NumaNode {
+            node_id: node.os_index,
+            logical_cores: cpus(node),
+        })
+        .filter(|node| !node.logical_cores.is_empty())
+        .collect();
+
+    Ok(HwlocTopology {
+        logical_cores: topology.objects(HWLOC_OBJ_PU).len(),
+        physical_cores: topology.objects(HWLOC_OBJ_CORE).len(),
+        l1_cache_data: cache(&topology, HWLOC_OBJ_L1CACHE),
+        l1_cache_instruction: cache(&topology, HWLOC_OBJ_L1ICACHE),
+        l2_cache: cache(&topology, HWLOC_OBJ_L2CACHE),
+        l3_cache: cache(&topology, HWLOC_OBJ_L3CACHE),
+        l4_cache: cache(&topology, HWLOC_OBJ_L4CACHE),
+        numa_nodes,
+    })
+}
+
+#[cfg(all(test, target_pointer_width = "64"))]
+mod tests {
+    use super::*;
+    use std::mem;
+
+    #[test]
+    fn object_layout() {
+        // Offsets of `struct hwloc_obj` and `struct hwloc_cache_attr_s` of hwloc 2 on LP64.
+        assert_eq!(mem::offset_of!(HwlocObj, os_index), 16);
+        assert_eq!(mem::offset_of!(HwlocObj, attr), 40);
+        assert_eq!(mem::offset_of!(HwlocObj, parent), 72);
+        assert_eq!(mem::offset_of!(HwlocObj, arity), 104);
+        assert_eq!(mem::offset_of!(HwlocObj, memory_first_child), 144);
+        assert_eq!(mem::offset_of!(HwlocObj, cpuset), 184);
+        assert_eq!(mem::offset_of!(HwlocCacheAttr, linesize), 12);
+        assert_eq!(mem::offset_of!(HwlocCacheAttr, associativity), 16);
+    }
+}
@@ -2,9 +2,111 @@
 //!
 //! A `PhysicalDevice` represents a (usually) physical machine instance with a CPU.
 //! It can be used to query feature support and available resources for the device.
+//!
+//! Without the default `std` feature the crate is `no_std` (requires `alloc`) and only
+//! exposes the properties derivable from CPUID via `PhysicalDeviceProperties::from_cpuid`.
+//!
+//! The optional `tracing` feature logs the queried CPUID leaves, OS values and
+//! detected properties to help diagnosing wrong results.
+//!
+//! The optional `async` feature adds `PhysicalDevice::enumerate_async`, running the
+//! property detection on a dedicated thread instead of `tokio::task::spawn_blocking`.
+//! It doesn't depend on a specific async runtime.
+//!
+//! The optional `hwloc` feature discovers the cores, caches and NUMA nodes via
+//! `libhwloc` (version 2) instead of the native CPUID and OS queries.
+//!
+//! The opt-in `env-override` feature replaces detected properties by the values of
+//! the JSON file at `NORSE_DEVICE_OVERRIDE` or single `NORSE_<FIELD>` variables
+//! (e.g. `NORSE_PHYSICAL_CORES=128`), to simulate other hardware in tests.
+//! `<FIELD>` is the upper case name of a `PhysicalDeviceProperties` field, the value is
+//! parsed as JSON, falling back to a string (e.g. `NORSE_DEVICE=Simulated CPU`).
+//! Single variables take precedence over the file, other `NORSE_` variables are ignored.
+//! It must not be enabled in production builds.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+// Not every target and feature combination uses all of the logging macros.
+
+/// Emit a `tracing` event at the given level, compiled out without the `tracing` feature.
+#[allow(unused_macros)]
+macro_rules! event {
+    ($level:ident, $($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        {
+            tracing::$level!($($arg)*);
+        }
+    };
+}
+
+#[allow(unused_macros)]
+macro_rules! trace {
+    ($($arg:tt)*) => { event!(trace, $($arg)*) };
+}
+
+#[allow(unused_macros)]
+macro_rules! debug {
+    ($($arg:tt)*) => { event!(debug, $($arg)*) };
+}
+
+#[allow(unused_macros)]
+macro_rules! warn {
+    ($($arg:tt)*) => { event!(warn, $($arg)*) };
+}
 
+#[cfg(feature = "std")]
+mod affinity;
+#[cfg(feature = "async")]
+mod blocking;
+mod builder;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod cpuid;
+#[cfg(feature = "std")]
 mod device;
+mod error;
+mod features;
+#[cfg(feature = "hwloc")]
+mod hwloc;
+#[cfg(feature = "env-override")]
+mod overrides;
+#[cfg(feature = "std")]
+mod profile;
 mod properties;
+mod report;
+#[cfg(feature = "std")]
+mod requirements;
+#[cfg(all(feature = "std", any(target_os = "macos", target_os = "freebsd")))]
+mod sysctl;
+#[cfg(all(
+    feature = "std",
+    target_os = "linux",
+    any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")
+))]
+mod sysfs;
+#[cfg(feature = "std")]
+mod topology;
+#[cfg(all(
+    feature = "std",
+    target_os = "windows",
+    any(target_arch = "x86", target_arch = "x86_64")
+))]
+mod windows;
 
+pub use crate::builder::*;
+#[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))]
+pub use crate::cpuid::arch::CpuidResult;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub use crate::cpuid::cpuid_available;
+#[cfg(feature = "std")]
 pub use crate::device::*;
+pub use crate::error::*;
+pub use crate::features::*;
+#[cfg(feature = "std")]
+pub use crate::profile::*;
 pub use crate::properties::*;
+#[cfg(feature = "std")]
+pub use crate::requirements::*;
+#[cfg(feature = "std")]
+pub use crate::topology::*;
@@ -0,0 +1,64 @@
+//! Overrides of the detected properties via environment variables, for testing only.
+
+use crate::error::DeviceError;
+use crate::properties::PhysicalDeviceProperties;
+use serde_json::Value;
+use std::env;
+
+/// Path of a JSON file with (a subset of) the properties.
+const OVERRIDE_FILE: &str = "NORSE_DEVICE_OVERRIDE";
+/// Prefix of the variables overriding single properties, e.g. `NORSE_PHYSICAL_CORES`.
+const FIELD_PREFIX: &str = "NORSE_";
+
+/// Apply the overrides of the environment to the detected properties.
+///
+/// Fields of the override file are applied first, followed by the variables of single
+/// fields. The values of single fields are parsed as JSON, falling back to a string.
+pub(crate) fn apply(
+    properties: PhysicalDeviceProperties,
+) -> Result<PhysicalDeviceProperties, DeviceError> {
+    let invalid = |err: serde_json::Error| DeviceError::InvalidOverride(err.to_string());
+
+    let mut fields = match serde_json::to_value(&properties).map_err(invalid)? {
+        Value::Object(fields) => fields,
+        _ => return Err(DeviceError::UnexpectedLayout),
+    };
+
+    let mut overridden = false;
+    if let Some(path) = env::var_os(OVERRIDE_FILE) {
+        let content = std::fs::read_to_string(&path).map_err(|err| {
+            DeviceError::InvalidOverride(format!("{}: {}", path.to_string_lossy(), err))
+        })?;
+        match serde_json::from_str(&content).map_err(invalid)? {
+            Value::Object(overrides) => fields.extend(overrides),
+            _ => {
+                return Err(DeviceError::InvalidOverride(String::from(
+                    "override file must contain an object",
+                )))
+            }
+        }
+        overridden = true;
+    }
+
+    let names = fields.keys().cloned().collect::<Vec<_>>();
+    for name in names {
+        let var = format!("{}{}", FIELD_PREFIX, name.to_uppercase());
+        if let Ok(value) = env::var(&var) {
+            let value = serde_json::from_str(&value).unwrap_or(Value::String(value));
+            fields.insert(name, value);
+            overridden = true;
+        }
+    }
+
+    if !overridden {
+        return Ok(properties);
+    }
+
+    let properties = serde_json::from_value::<PhysicalDeviceProperties>(Value::Object(fields))
+        .map_err(invalid)?;
+    warn!(
+        ?properties,
+        "detected properties overridden by the environment"
+    );
+    Ok(properties)
+}
@@ -0,0 +1,168 @@
+use crate::device::PhysicalDevice;
+use crate::features::PhysicalDeviceFeatures;
+use crate::properties::Vendor;
+use std::fmt;
+
+/// Expected properties of a known machine, e.g. a CI runner.
+///
+/// Fields which are `None` are not compared.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct PhysicalDeviceProfile {
+    /// Name of the profile.
+    pub name: String,
+    /// Vendor of the processor.
+    pub vendor: Option<Vendor>,
+    /// Name of the device.
+    pub device: Option<String>,
+    /// Processor family, including the extended family.
+    pub family: Option<u32>,
+    /// Processor model, including the extended model.
+    pub model: Option<u32>,
+    /// Number of logical cores.
+    pub logical_cores: Option<usize>,
+    /// Number of physical cores.
+    pub physical_cores: Option<usize>,
+    /// Size of the L1 data cache in bytes.
+    pub l1_cache_data_bytes: Option<u32>,
+    /// Size of the L1 instruction cache in bytes.
+    pub l1_cache_instruction_bytes: Option<u32>,
+    /// Size of the L2 cache in bytes.
+    pub l2_cache_bytes: Option<u32>,
+    /// Size of the L3 cache in bytes.
+    pub l3_cache_bytes: Option<u32>,
+    /// Total physical memory in bytes.
+    pub total_memory_bytes: Option<u64>,
+    /// Supported features, compared flag by flag.
+    pub features: Option<PhysicalDeviceFeatures>,
+}
+
+/// Field differing from the expected profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDiff {
+    /// Name of the field, features are reported as `features.<name>`.
+    pub field: String,
+    /// Value of the profile.
+    pub expected: String,
+    /// Value of the device.
+    pub actual: String,
+}
+
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: expected {}, found {}",
+            self.field, self.expected, self.actual
+        )
+    }
+}
+
+impl PhysicalDevice {
+    /// Compare the device against the profile, returning all differing fields.
+    pub fn diff(&self, profile: &PhysicalDeviceProfile) -> Vec<FieldDiff> {
+        fn compare<T: PartialEq + fmt::Display>(
+            diffs: &mut Vec<FieldDiff>,
+            field: &str,
+            expected: Option<&T>,
+            actual: &T,
+        ) {
+            if let Some(expected) = expected {
+                if expected != actual {
+                    diffs.push(FieldDiff {
+                        field: String::from(field),
+                        expected: expected.to_string(),
+                        actual: actual.to_string(),
+                    });
+                }
+            }
+        }
+
+        let properties = self.properties();
+        let mut diffs = Vec::new();
+
+        compare(
+            &mut diffs,
+            "vendor",
+            profile.vendor.as_ref(),
+            &properties.vendor,
+        );
+        compare(
+            &mut diffs,
+            "device",
+            profile.device.as_ref(),
+            &properties.device,
+        );
+        compare(
+            &mut diffs,
+            "family",
+            profile.family.as_ref(),
+            &properties.family,
+        );
+        compare(
+            &mut diffs,
+            "model",
+            profile.model.as_ref(),
+            &properties.model,
+        );
+        compare(
+            &mut diffs,
+            "logical_cores",
+            profile.logical_cores.as_ref(),
+            &properties.logical_cores,
+        );
+        compare(
+            &mut diffs,
+            "physical_cores",
+            profile.physical_cores.as_ref(),
+            &properties.physical_cores,
+        );
+        compare(
+            &mut diffs,
+            "l1_cache_data_bytes",
+            profile.l1_cache_data_bytes.as_ref(),
+            &properties.l1_cache_data.size,
+        );
+        compare(
+            &mut diffs,
+            "l1_cache_instruction_bytes",
+            profile.l1_cache_instruction_bytes.as_ref(),
+            &properties.l1_cache_instruction.size,
+        );
+        compare(
+            &mut diffs,
+            "l2_cache_bytes",
+            profile.l2_cache_bytes.as_ref(),
+            &properties.l2_cache.size,
+        );
+        compare(
+            &mut diffs,
+            "l3_cache_bytes",
+            profile.l3_cache_bytes.as_ref(),
+            &properties.l3_cache.size,
+        );
+        compare(
+            &mut diffs,
+            "total_memory_bytes",
+            profile.total_memory_bytes.as_ref(),
+            &properties.total_memory_bytes,
+        );
+
+        if let Some(features) = &profile.features {
+            let expected = features.flags();
+            let actual = self.features().flags();
+            for ((name, expected), (_, actual)) in expected.into_iter().zip(actual) {
+                if expected != actual {
+                    diffs.push(FieldDiff {
+                        field: format!("features.{}", name),
+                        expected: expected.to_string(),
+                        actual: actual.to_string(),
+                    });
+                }
+            }
+        }
+
+        diffs
+    }
+}
@@ -1,4 +1,18 @@
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg(feature = "std")]
+use crate::error::DeviceError;
+#[cfg(feature = "std")]
+use crate::topology::{CacheInstance, Topology};
+use alloc::format;
+use alloc::string::{String, ToString};
+#[cfg(feature = "std")]
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+
+/// Physical Device Cache Properties
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PhysicalDeviceCacheProperties {
     /// Size of cache in bytes.
     ///
@@ -6,297 +20,3373 @@ pub struct PhysicalDeviceCacheProperties {
     /// For L3 caches the size is specified per physical package.
     ///
     /// May be `0` if information couldn't be retrieved.
+    /// Saturates at `u32::MAX` for caches of 4 GiB and more, see `size_kib`.
     pub size: u32,
 
     /// Size of cache line in bytes.
     ///
     /// May be `0` if information couldn't be retrieved.
     pub line_size: u32,
+
+    /// Number of ways of associativity.
+    ///
+    /// Fully associative caches report one way per cache line.
+    ///
+    /// May be `0` if information couldn't be retrieved.
+    pub associativity: u32,
+
+    /// Number of sets.
+    ///
+    /// May be `0` if information couldn't be retrieved.
+    pub sets: u32,
+
+    /// Whether the cache contains the lines of the lower cache levels.
+    pub inclusivity: CacheInclusivity,
+
+    /// Whether stores are written to the next level immediately or on eviction.
+    ///
+    /// Only exposed by Linux sysfs on some architectures (e.g. from the device tree),
+    /// `Unknown` otherwise.
+    pub write_policy: CacheWritePolicy,
+
+    /// Number of logical cores sharing a single instance of the cache.
+    ///
+    /// `1` denotes a cache private to a logical core. Exact if read from the OS
+    /// (e.g. sysfs `shared_cpu_list` on Linux), an upper bound if derived from CPUID
+    /// which reports the number of addressable logical core IDs.
+    /// May be `0` if information couldn't be retrieved.
+    pub shared_by_cores: u32,
+}
+
+/// Cache Inclusivity
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CacheInclusivity {
+    /// Contains all lines of the lower cache levels.
+    Inclusive,
+    /// Doesn't contain (all) lines of the lower cache levels, e.g. victim caches.
+    Exclusive,
+    #[default]
+    Unknown,
+}
+
+/// Cache Write Policy
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CacheWritePolicy {
+    /// Stores are written to the next level when the line is evicted.
+    WriteBack,
+    /// Stores are written to the next level immediately.
+    WriteThrough,
+    #[default]
+    Unknown,
 }
 
-impl std::default::Default for PhysicalDeviceCacheProperties {
-    fn default() -> Self {
+/// Placeholder for optional caches which aren't present.
+const ABSENT_CACHE: PhysicalDeviceCacheProperties = PhysicalDeviceCacheProperties {
+    size: 0,
+    line_size: 0,
+    associativity: 0,
+    sets: 0,
+    inclusivity: CacheInclusivity::Unknown,
+    write_policy: CacheWritePolicy::Unknown,
+    shared_by_cores: 0,
+};
+
+impl PhysicalDeviceCacheProperties {
+    /// Returns if the cache exists, i.e. has a known non-zero size.
+    pub fn is_present(&self) -> bool {
+        self.size > 0
+    }
+
+    /// Size of the cache in KiB.
+    ///
+    /// Derived from the geometry if the byte size saturated.
+    pub fn size_kib(&self) -> u64 {
+        let geometry = self.sets as u64 * self.associativity as u64 * self.line_size as u64;
+        if self.size == u32::MAX && geometry > self.size as u64 {
+            geometry >> 10
+        } else {
+            self.size as u64 >> 10
+        }
+    }
+
+    /// Total size in bytes of the cache instances of `physical_cores` cores.
+    ///
+    /// Assumes one instance per physical core, as for the L1 and L2 caches.
+    /// L3 sizes are already specified per package.
+    pub fn total_size_across_cores(&self, physical_cores: usize) -> u64 {
+        self.size as u64 * physical_cores as u64
+    }
+
+    /// Discard implausible values reported by buggy firmware or hypervisors.
+    ///
+    /// Returns an absent cache if the size exceeds the bound of the `level`
+    /// (L1 1 MiB, L2 256 MiB, L3 2 GiB) or the line size isn't a power of two
+    /// between 16 and 256 bytes.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    pub(crate) fn sanitized(self, level: u32) -> Self {
+        let max_size: u32 = match level {
+            1 => 1 << 20,
+            2 => 256 << 20,
+            _ => 2 << 30,
+        };
+        let plausible = self.size <= max_size
+            && self.line_size.is_power_of_two()
+            && (16..=256).contains(&self.line_size);
+        if !self.is_present() || plausible {
+            self
+        } else {
+            debug!(level, cache = ?self, "discarding implausible cache");
+            PhysicalDeviceCacheProperties::default()
+        }
+    }
+
+    /// Cache properties with the number of sets derived from the other parameters.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64", feature = "hwloc"))]
+    pub(crate) fn from_geometry(size: u32, line_size: u32, associativity: u32) -> Self {
+        let sets = line_size
+            .checked_mul(associativity)
+            .and_then(|way_size| size.checked_div(way_size))
+            .unwrap_or(0);
         PhysicalDeviceCacheProperties {
-            size: 0,
-            line_size: 0,
+            size,
+            line_size,
+            associativity,
+            sets,
+            inclusivity: CacheInclusivity::Unknown,
+            write_policy: CacheWritePolicy::Unknown,
+            shared_by_cores: 0,
         }
     }
 }
 
+/// Kind of cached content.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CacheKind {
+    Data,
+    Instruction,
+    Unified,
+}
+
+/// Cache level and kind with the properties of the cache.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CacheDescriptor<'a> {
+    /// Cache level, starting at `1`.
+    pub level: u8,
+    /// Kind of cached content.
+    pub kind: CacheKind,
+    /// Properties of the cache.
+    pub properties: &'a PhysicalDeviceCacheProperties,
+}
+
+/// Cache capacity available to each hardware thread when all threads are busy.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EffectiveCachePerThread {
+    /// L2 cache bytes per thread, `0` if unknown.
+    pub l2_per_thread: u32,
+    /// L3 cache bytes per thread, `0` if unknown.
+    pub l3_per_thread: u32,
+}
+
+/// Physical Device TLB Properties
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhysicalDeviceTlbProperties {
+    /// Number of entries.
+    ///
+    /// May be `0` if information couldn't be retrieved.
+    pub entries: u32,
+
+    /// Size of the pages covered by the entries in bytes.
+    ///
+    /// May be `0` if information couldn't be retrieved.
+    pub page_size_bytes: u32,
+}
+
 /// Physical Device Properties
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PhysicalDeviceProperties {
     /// Device Hardware Vendor
     pub vendor: Vendor,
     /// Name of the device.
     pub device: String,
+    /// Processor family, including the extended family.
+    ///
+    /// May be `0` if information couldn't be retrieved.
+    pub family: u32,
+    /// Processor model, including the extended model.
+    pub model: u32,
+    /// Processor stepping.
+    pub stepping: u32,
+    /// Microarchitecture name (e.g. `Zen 3`, `Skylake`).
+    ///
+    /// `None` if the family and model are not known.
+    pub microarchitecture: Option<String>,
     /// Number of logical cores.
     pub logical_cores: usize,
     /// Number of physical cores.
     pub physical_cores: usize,
+    /// Number of hardware threads per physical core.
+    ///
+    /// `1` if simultaneous multithreading (SMT) is not available or disabled.
+    pub threads_per_core: usize,
+    /// Number of physical packages (sockets).
+    ///
+    /// Not reduced by the affinity mask of the process.
+    /// `1` if the package topology isn't exposed by the OS, and for each
+    /// device returned by `PhysicalDevice::enumerate_all`.
+    pub package_count: usize,
     /// Properties of the L1 Data Cache.
     pub l1_cache_data: PhysicalDeviceCacheProperties,
     /// Properties of the L1 Instruction Cache.
     pub l1_cache_instruction: PhysicalDeviceCacheProperties,
+    /// Properties of the L1 Unified Cache, for devices not splitting the L1 cache.
+    ///
+    /// The L1 data and instruction caches are absent if set.
+    pub l1_cache_unified: Option<PhysicalDeviceCacheProperties>,
     /// Properties of the L2 Unified Cache.
     pub l2_cache: PhysicalDeviceCacheProperties,
     /// Properties of the L3 Unified Cache.
     pub l3_cache: PhysicalDeviceCacheProperties,
+    /// Properties of the L4 Unified Cache, e.g. the eDRAM of Crystalwell.
+    ///
+    /// Absent (size `0`) on most devices.
+    pub l4_cache: PhysicalDeviceCacheProperties,
+    /// Recommended software prefetch distance in bytes.
+    ///
+    /// Twice the L2 cache line size (or the dominant line size if unknown),
+    /// `0` if no line size is known.
+    pub recommended_prefetch_bytes: u32,
+    /// Properties of the L1 Data TLB.
+    pub l1_tlb_data: PhysicalDeviceTlbProperties,
+    /// Properties of the L1 Instruction TLB.
+    pub l1_tlb_instruction: PhysicalDeviceTlbProperties,
+    /// Properties of the L2 (shared) TLB.
+    pub l2_tlb: PhysicalDeviceTlbProperties,
+    /// Base frequency in MHz.
+    ///
+    /// May be `0` if information couldn't be retrieved.
+    pub base_frequency_mhz: u32,
+    /// Maximum (turbo) frequency in MHz.
+    ///
+    /// May be `0` if information couldn't be retrieved.
+    pub max_frequency_mhz: u32,
+    /// Current frequency of the first logical core in MHz at the time of the query.
+    ///
+    /// Updated by `refresh`, may be `0` if information couldn't be retrieved.
+    pub current_frequency_mhz: u32,
+    /// Whether turbo boost (frequency scaling above the base frequency) is enabled.
+    ///
+    /// `None` if it couldn't be determined.
+    pub turbo_enabled: Option<bool>,
+    /// Whether the time stamp counter runs at a constant rate across frequency changes
+    /// and sleep states.
+    ///
+    /// `false` for non-x86 devices or if it couldn't be determined.
+    pub invariant_tsc: bool,
+    /// Thermal design power of a package in watts.
+    ///
+    /// Only available on Linux via RAPL or the `MSR_PKG_POWER_INFO` MSR.
+    pub tdp_watts: Option<u32>,
+    /// Total physical memory in bytes.
+    ///
+    /// May be `0` if information couldn't be retrieved.
+    pub total_memory_bytes: u64,
+    /// Currently available physical memory in bytes.
+    ///
+    /// Includes memory reclaimable without swapping, e.g. inactive pages and caches.
+    /// May be `0` if information couldn't be retrieved.
+    pub available_memory_bytes: u64,
+
+    /// Supported page sizes in bytes beyond the base page size, in ascending order.
+    ///
+    /// Empty if only base pages are supported.
+    pub large_page_sizes: Vec<u64>,
+    /// Clusters of cores sharing the same core type.
+    ///
+    /// Contains a single cluster of type `Unknown` covering all logical cores
+    /// for devices without hybrid core architecture.
+    pub core_clusters: Vec<CoreCluster>,
+    /// Core complexes (CCX) of AMD Zen CPUs, cores sharing a slice of the L3 cache.
+    ///
+    /// Empty for other vendors or if the topology extensions aren't available.
+    pub core_complexes: Vec<CoreComplex>,
+    /// NUMA nodes of the device.
+    ///
+    /// Contains a single node covering all logical cores if the system
+    /// doesn't expose NUMA information.
+    pub numa_nodes: Vec<NumaNode>,
+
+    /// Hypervisor the device is running in.
+    ///
+    /// `None` if running on bare metal or the hypervisor isn't exposed.
+    pub hypervisor: Option<Hypervisor>,
+    /// Binary translator or emulator the process is running in.
+    ///
+    /// The SIMD feature flags reported while translated describe the emulated CPU
+    /// and may not match the real hardware, e.g. AVX paths may be slow or unavailable.
+    /// `None` if running natively.
+    pub translation: Option<Translation>,
+    /// Confidential computing and memory encryption capabilities.
+    pub security_features: SecurityFeatures,
+    /// Performance monitoring counters of the architectural PMU.
+    pub pmu_counters: PmuInfo,
+
+    /// Byte order of the device.
+    pub endianness: Endianness,
+    /// Width of a pointer in bits.
+    pub pointer_width_bits: u8,
 }
 
-impl PhysicalDeviceProperties {
-    fn system_cpuid_vendor() -> Vendor {
-        let brand = {
-            let cpuid = unsafe { std::arch::x86_64::__cpuid(0) };
-            let mut data = [0u8; 12];
-            data[0..4].copy_from_slice(unsafe { &std::mem::transmute::<_, [u8; 4]>(cpuid.ebx) });
-            data[4..8].copy_from_slice(unsafe { &std::mem::transmute::<_, [u8; 4]>(cpuid.edx) });
-            data[8..12].copy_from_slice(unsafe { &std::mem::transmute::<_, [u8; 4]>(cpuid.ecx) });
-            data
-        };
+/// NUMA Node
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NumaNode {
+    /// OS index of the node.
+    pub node_id: u32,
+    /// OS indices of the logical cores belonging to the node.
+    pub logical_cores: Vec<usize>,
+}
 
-        match &brand {
-            b"AuthenticAMD" => Vendor::AMD,
-            b"GenuineIntel" => Vendor::Intel,
-            _ => Vendor::Unknown,
-        }
-    }
+/// Logical cores (SMT siblings) of a physical core.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoreAffinity {
+    /// OS indices of the logical cores, usable for setting thread affinity.
+    pub logical_cores: Vec<usize>,
+}
 
-    fn system_cpuid_vendor_device() -> (Vendor, String) {
-        let vendor = Self::system_cpuid_vendor();
-        let device = match vendor {
-            Vendor::AMD => {
-                let name = {
-                    let extract = |v: u32| -> [char; 4] {
-                        [
-                            (v & 0xFF) as u8 as _,
-                            ((v >> 8) & 0xFF) as u8 as _,
-                            ((v >> 16) & 0xFF) as u8 as _,
-                            ((v >> 24) & 0xFF) as u8 as _,
-                        ]
-                    };
+/// Logical and physical cores of a physical package (socket).
+#[cfg(all(
+    feature = "std",
+    any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")
+))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Package {
+    pub logical_cores: Vec<usize>,
+    pub physical_cores: usize,
+}
 
-                    let mut name = String::new();
-                    'name: for i in 2..=4 {
-                        let raw = unsafe { std::arch::x86_64::__cpuid(0x80000000 + i) };
-
-                        let chars = [
-                            extract(raw.eax),
-                            extract(raw.ebx),
-                            extract(raw.ecx),
-                            extract(raw.edx),
-                        ];
-
-                        for quad in &chars {
-                            for c in quad {
-                                if *c == '\0' {
-                                    break 'name;
-                                }
-
-                                name.push(*c);
-                            }
-                        }
-                    }
-                    name
-                };
+/// Core Cluster
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoreCluster {
+    /// Type of the cores in the cluster.
+    pub core_type: CoreType,
+    /// OS indices of the logical cores belonging to the cluster.
+    pub logical_cores: Vec<usize>,
+}
 
-                name.trim_end().to_owned()
-            }
-            _ => String::new(),
-        };
+/// Strategy for detecting the properties of the current system.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum DetectionBackend {
+    /// Combine the OS and CPUID, preferring the OS where both are available
+    /// (e.g. sysfs caches on Linux) and hwloc with the `hwloc` feature.
+    #[default]
+    Auto,
+    /// Only use CPUID (x86), the core counts and memory are still queried from the OS.
+    Cpuid,
+    /// Require the caches of sysfs (Linux), without falling back to CPUID.
+    Sysfs,
+    /// Require the topology of hwloc (`hwloc` feature).
+    Hwloc,
+}
 
-        (vendor, device)
-    }
+/// Confidential computing and memory encryption capabilities.
+///
+/// All `false` for non-x86 devices or if the CPUID leaves are absent.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SecurityFeatures {
+    /// Intel Software Guard Extensions (SGX), supported and enabled.
+    pub sgx: bool,
+    /// Maximum enclave size in bytes outside of 64-bit mode, `0` without SGX.
+    pub sgx_max_enclave_bytes_32bit: u64,
+    /// Maximum enclave size in bytes in 64-bit mode, `0` without SGX.
+    pub sgx_max_enclave_bytes_64bit: u64,
+    /// Total size of the enclave page cache (EPC) sections in bytes, `0` without SGX.
+    pub sgx_epc_bytes: u64,
+    /// AMD Secure Encrypted Virtualization (SEV).
+    pub sev: bool,
+    /// Total memory encryption, Intel TME (incl. MKTME) or AMD SME.
+    pub tme: bool,
+}
 
-    #[cfg(target_os = "windows")]
-    pub fn system() -> Self {
-        use std::ptr;
-        use winapi::um::sysinfoapi::*;
-        use winapi::um::winnt::*;
+/// Performance Monitoring Unit
+///
+/// Architectural performance monitoring of CPUID leaf `0xA`.
+/// All `0` if the leaf is unsupported, e.g. on AMD or non-x86 devices.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PmuInfo {
+    /// Version of the architectural performance monitoring.
+    pub version: u8,
+    /// Number of general-purpose counters per logical core.
+    pub general_purpose_counters: u8,
+    /// Bit width of the general-purpose counters.
+    pub general_purpose_counter_bits: u8,
+    /// Number of fixed-function counters per logical core.
+    pub fixed_counters: u8,
+    /// Bit width of the fixed-function counters.
+    pub fixed_counter_bits: u8,
+}
 
-        let mut length = 0;
-        unsafe {
-            GetLogicalProcessorInformation(ptr::null_mut(), &mut length);
-        }
-        let info_size = std::mem::size_of::<SYSTEM_LOGICAL_PROCESSOR_INFORMATION>() as u32;
-        assert_eq!(length % info_size, 0);
-        let num_infos = length / info_size;
+/// Core Complex (CCX)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoreComplex {
+    /// OS indices of the logical cores belonging to the complex.
+    pub logical_cores: Vec<usize>,
+    /// Size of the L3 slice shared by the cores in bytes.
+    pub l3_cache_bytes: u32,
+}
 
-        let mut infos = Vec::with_capacity(num_infos as _);
-        unsafe {
-            GetLogicalProcessorInformation(infos.as_mut_ptr(), &mut length);
-        }
-        unsafe {
-            infos.set_len(num_infos as _);
-        }
+/// Core Type of hybrid architectures.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CoreType {
+    /// Performance cores (e.g. Intel P-cores).
+    Performance,
+    /// Efficient cores (e.g. Intel E-cores).
+    Efficient,
+    Unknown,
+}
 
-        // TODO: multi socket support, general better handling
+/// Frequency penalty of heavy vector instruction use.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Penalty {
+    /// Clock speed is sustained.
+    None,
+    /// Clock speed drops slightly.
+    Light,
+    /// Clock speed drops significantly, narrower vectors may be faster overall.
+    Heavy,
+    Unknown,
+}
 
-        let mut logical_cores = 0;
-        let mut physical_cores = 0;
-        let mut l1_cache_instruction = PhysicalDeviceCacheProperties::default();
-        let mut l1_cache_data = PhysicalDeviceCacheProperties::default();
-        let mut l2_cache = PhysicalDeviceCacheProperties::default();
-        let mut l3_cache = PhysicalDeviceCacheProperties::default();
+/// Coarse category of a device.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DeviceClass {
+    /// Laptops and other mobile devices.
+    Laptop,
+    Desktop,
+    Server,
+    /// Embedded and low power devices.
+    Embedded,
+    Unknown,
+}
 
-        #[allow(non_upper_case_globals)]
-        for info in infos {
-            match info.Relationship {
-                RelationProcessorCore => {
-                    physical_cores += 1;
-                }
-                RelationProcessorPackage => {
-                    logical_cores += info.ProcessorMask.count_ones() as usize;
-                }
-                RelationCache => {
-                    let cache = unsafe { info.u.Cache() };
+/// Kind of work a thread pool runs, see `recommended_thread_count`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Workload {
+    /// Compute-bound work, hardware threads of a core compete for its execution units.
+    CpuBound,
+    /// I/O-bound work, threads mostly wait.
+    IoBound,
+}
 
-                    let properties = PhysicalDeviceCacheProperties {
-                        size: cache.Size as _,
-                        line_size: cache.LineSize as _,
-                    };
-                    let cache = match (cache.Level, cache.Type) {
-                        (1, CacheInstruction) => &mut l1_cache_instruction,
-                        (1, CacheData) => &mut l1_cache_data,
-                        (2, CacheUnified) => &mut l2_cache,
-                        (3, CacheUnified) => &mut l3_cache,
-                        _ => continue,
-                    };
+impl NumaNode {
+    /// Single node covering all logical cores.
+    #[cfg(all(
+        feature = "std",
+        any(
+            target_arch = "x86",
+            target_arch = "x86_64",
+            target_arch = "aarch64",
+            target_family = "wasm"
+        )
+    ))]
+    pub(crate) fn uniform(logical_cores: usize) -> Vec<NumaNode> {
+        vec![NumaNode {
+            node_id: 0,
+            logical_cores: (0..logical_cores).collect(),
+        }]
+    }
+}
 
-                    cache.size += properties.size;
-                    cache.line_size = properties.line_size;
-                }
-                _ => {}
-            }
+impl PhysicalDeviceProperties {
+    /// Query the properties derivable from CPUID alone, without touching the OS.
+    ///
+    /// Core counts, memory and topology are not available and reported as `0` or empty,
+    /// the threads per core are read from the CPUID topology leaves.
+    /// All CPUID derived values are unknown if CPUID is unavailable, see `cpuid_available`.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    pub fn from_cpuid() -> Self {
+        if !crate::cpuid::cpuid_available() {
+            return PhysicalDeviceProperties::builder().build();
         }
 
-        l1_cache_instruction.size /= physical_cores;
-        l1_cache_data.size /= physical_cores;
-        l2_cache.size /= physical_cores;
-
         let (vendor, device) = Self::system_cpuid_vendor_device();
+        let leaf1_eax = crate::cpuid::cpuid(1, 0).map_or(0, |leaf1| leaf1.eax);
+        let (family, model, stepping) = cpuid_family_model_stepping(leaf1_eax);
+        let microarchitecture = microarchitecture(vendor, family, model).map(String::from);
+        let hypervisor = Self::system_hypervisor();
+        let translation = cpuid_translation(&device, hypervisor.as_ref());
+        let (l1_cache_data, l1_cache_instruction, l2_cache, l3_cache, l4_cache, l1_cache_unified) =
+            Self::cpuid_caches(vendor);
+        let crate::cpuid::Tlbs {
+            l1_data: l1_tlb_data,
+            l1_instruction: l1_tlb_instruction,
+            l2: l2_tlb,
+        } = Self::system_tlbs(vendor);
+        let (mut base_frequency_mhz, max_frequency_mhz) = Self::cpuid_frequency(vendor);
+        if base_frequency_mhz == 0 {
+            base_frequency_mhz = parse_brand_frequency(&device).unwrap_or(0);
+        }
 
-        PhysicalDeviceProperties {
+        let mut properties = PhysicalDeviceProperties {
             vendor,
             device,
-            logical_cores,
-            physical_cores: physical_cores as _,
+            family,
+            model,
+            stepping,
+            microarchitecture,
+            logical_cores: 0,
+            physical_cores: 0,
+            threads_per_core: Self::cpuid_threads_per_core(vendor).unwrap_or(1),
+            package_count: 1,
             l1_cache_data,
             l1_cache_instruction,
+            l1_cache_unified: Some(l1_cache_unified)
+                .filter(PhysicalDeviceCacheProperties::is_present),
             l2_cache,
             l3_cache,
+            l4_cache,
+            recommended_prefetch_bytes: 0,
+            l1_tlb_data,
+            l1_tlb_instruction,
+            l2_tlb,
+            base_frequency_mhz,
+            max_frequency_mhz,
+            current_frequency_mhz: 0,
+            turbo_enabled: None,
+            invariant_tsc: Self::cpuid_invariant_tsc(),
+            tdp_watts: None,
+            total_memory_bytes: 0,
+            available_memory_bytes: 0,
+            large_page_sizes: Self::cpuid_large_page_sizes(),
+            core_clusters: Vec::new(),
+            core_complexes: Vec::new(),
+            numa_nodes: Vec::new(),
+            hypervisor,
+            translation,
+            security_features: crate::cpuid::security_features(),
+            pmu_counters: crate::cpuid::pmu_info(),
+            endianness: Endianness::native(),
+            pointer_width_bits: usize::BITS as u8,
+        };
+        properties.recommended_prefetch_bytes = properties.default_prefetch_bytes();
+        properties
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn system_cpuid_vendor() -> Vendor {
+        match crate::cpuid::cpuid(0, 0) {
+            Some(cpuid) => cpuid_vendor(cpuid.ebx, cpuid.edx, cpuid.ecx),
+            None => Vendor::Unknown,
         }
     }
 
-    #[cfg(not(target_os = "windows"))]
-    pub fn system() -> Self {
-        use std::ops::Range;
+    /// Read the hypervisor signature from leaf `0x40000000` if the hypervisor bit is set.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn system_hypervisor() -> Option<Hypervisor> {
+        let cpuid = crate::cpuid::cpuid(0x40000000, 0)?;
+        Some(cpuid_hypervisor(cpuid.ebx, cpuid.ecx, cpuid.edx))
+    }
 
-        fn extract_bits(v: u32, bits: Range<u8>) -> u32 {
-            let num_bits = bits.end - bits.start;
-            let mask = (1 << num_bits) - 1;
-            (v >> bits.start) & mask
+    /// Read the brand string from the extended CPUID leaves `0x80000002..=0x80000004`.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn system_cpuid_brand() -> String {
+        let mut registers = [0; 12];
+        for (i, leaf) in (0x80000002..=0x80000004).enumerate() {
+            let raw = match crate::cpuid::cpuid(leaf, 0) {
+                Some(raw) => raw,
+                None => return String::new(),
+            };
+            registers[i * 4..i * 4 + 4].copy_from_slice(&[raw.eax, raw.ebx, raw.ecx, raw.edx]);
         }
 
-        let (device, vendor) = Self::system_cpuid_vendor();
+        cpuid_brand(&registers)
+    }
 
-        let (l1_cache_data, l1_cache_instruction, l2_cache, l3_cache) = match vendor {
-            Vendor::AMD => {
-                let l1_cache = unsafe { std::arch::x86_64::__cpuid(0x80000005) };
-                let l1_cache_instruction = PhysicalDeviceCacheProperties {
-                    size: extract_bits(l1_cache.edx, 24..32) * 1024,
-                    line_size: extract_bits(l1_cache.edx, 0..8),
-                };
-                let l1_cache_data = PhysicalDeviceCacheProperties {
-                    size: extract_bits(l1_cache.ecx, 24..32) * 1024,
-                    line_size: extract_bits(l1_cache.ecx, 0..8),
-                };
+    /// Read the vendor and brand string.
+    ///
+    /// The brand string is also read for unknown vendors, as most x86 compatible
+    /// CPUs populate the extended leaves.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn system_cpuid_vendor_device() -> (Vendor, String) {
+        (Self::system_cpuid_vendor(), Self::system_cpuid_brand())
+    }
 
-                let l2_l3_cache = unsafe { std::arch::x86_64::__cpuid(0x80000006) };
-                let l2_cache = PhysicalDeviceCacheProperties {
-                    size: extract_bits(l2_l3_cache.ecx, 16..32) * 1024,
-                    line_size: extract_bits(l2_l3_cache.ecx, 0..8),
-                };
-                let l3_cache = PhysicalDeviceCacheProperties {
-                    size: extract_bits(l2_l3_cache.edx, 18..32) * 512 * 1024,
-                    line_size: extract_bits(l2_l3_cache.edx, 0..8),
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn system_tlbs(vendor: Vendor) -> crate::cpuid::Tlbs {
+        match vendor {
+            Vendor::AMD | Vendor::Hygon => crate::cpuid::amd_tlbs(),
+            Vendor::Intel | Vendor::Zhaoxin | Vendor::Via => crate::cpuid::intel_tlbs(),
+            _ => Default::default(),
+        }
+    }
+
+    /// Read the invariant TSC bit from leaf `0x80000007`.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn cpuid_invariant_tsc() -> bool {
+        crate::cpuid::cpuid(0x80000007, 0).is_some_and(|cpuid| cpuid.edx & (1 << 8) != 0)
+    }
+
+    /// Read the supported large page sizes from the PSE (2 MiB) and PDPE1GB (1 GiB) bits.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn cpuid_large_page_sizes() -> Vec<u64> {
+        let mut sizes = Vec::new();
+        if crate::cpuid::cpuid(1, 0).is_some_and(|cpuid| cpuid.edx & (1 << 3) != 0) {
+            sizes.push(2 << 20);
+        }
+        if crate::cpuid::cpuid(0x80000001, 0).is_some_and(|cpuid| cpuid.edx & (1 << 26) != 0) {
+            sizes.push(1 << 30);
+        }
+        sizes
+    }
+
+    /// Read the number of hardware threads per core from the SMT level of the x2APIC
+    /// topology (leaf `0x1F` or `0xB`), or the AMD extended topology leaf `0x8000001E`.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn cpuid_threads_per_core(vendor: Vendor) -> Option<usize> {
+        use crate::cpuid::cpuid;
+
+        for leaf in [0x1F, 0xB] {
+            for subleaf in 0..8 {
+                let level = match cpuid(leaf, subleaf) {
+                    Some(level) => level,
+                    None => break,
                 };
+                let level_type = (level.ecx >> 8) & 0xFF;
+                if level_type == 0 {
+                    break;
+                }
+                // Level type `1` denotes the SMT level.
+                if level_type == 1 && level.ebx & 0xFFFF != 0 {
+                    return Some((level.ebx & 0xFFFF) as usize);
+                }
+            }
+        }
 
-                (
-                    name.trim_end().to_owned(),
+        if matches!(vendor, Vendor::AMD | Vendor::Hygon) {
+            let topology = cpuid(0x8000001E, 0)?;
+            return Some((((topology.ebx >> 8) & 0xFF) + 1) as usize);
+        }
+        None
+    }
+
+    /// Read the base and maximum frequency in MHz from CPUID leaf `0x16` (Intel).
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn cpuid_frequency(vendor: Vendor) -> (u32, u32) {
+        match crate::cpuid::cpuid(0x16, 0) {
+            Some(frequency) if vendor == Vendor::Intel => {
+                (frequency.eax & 0xFFFF, frequency.ebx & 0xFFFF)
+            }
+            _ => (0, 0),
+        }
+    }
+
+    /// Read the cache properties from the CPUID cache leaves.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn cpuid_caches(
+        vendor: Vendor,
+    ) -> (
+        PhysicalDeviceCacheProperties,
+        PhysicalDeviceCacheProperties,
+        PhysicalDeviceCacheProperties,
+        PhysicalDeviceCacheProperties,
+        PhysicalDeviceCacheProperties,
+        PhysicalDeviceCacheProperties,
+    ) {
+        use crate::cpuid::{cpuid, parse_caches};
+
+        match vendor {
+            // Hygon CPUs are derived from AMD Zen and share the cache leaves.
+            Vendor::AMD | Vendor::Hygon => {
+                let mut caches: [PhysicalDeviceCacheProperties; 6] = Default::default();
+                for leaf in [0x80000005, 0x80000006] {
+                    let result = match cpuid(leaf, 0) {
+                        Some(result) => result,
+                        None => continue,
+                    };
+                    for cache in parse_caches(leaf, 0, &result) {
+                        insert_cache(&mut caches, cache.kind, cache.level, cache.properties);
+                    }
+                }
+                let [l1_cache_data, l1_cache_instruction, l2_cache, l3_cache, l4_cache, l1_cache_unified] =
+                    caches;
+                let mut caches = (
                     l1_cache_data,
                     l1_cache_instruction,
                     l2_cache,
                     l3_cache,
-                )
+                    l4_cache,
+                    l1_cache_unified,
+                );
+
+                let family =
+                    cpuid(1, 0).map_or(0, |leaf1| cpuid_family_model_stepping(leaf1.eax).0);
+                let (l2_inclusivity, l3_inclusivity) = amd_cache_inclusivity(family);
+                caches.2.inclusivity = l2_inclusivity;
+                caches.3.inclusivity = l3_inclusivity;
+
+                // Cache sharing is only exposed with topology extensions (leaf `0x8000001D`).
+                let topology_extensions =
+                    cpuid(0x80000001, 0).is_some_and(|cpuid| cpuid.ecx & (1 << 22) != 0);
+                if topology_extensions {
+                    for i in 0..8 {
+                        let cache = match cpuid(0x8000001D, i)
+                            .and_then(|result| parse_caches(0x8000001D, i, &result).pop())
+                        {
+                            Some(cache) => cache,
+                            None => break,
+                        };
+
+                        let cache_data = match (cache.kind, cache.level) {
+                            (CacheKind::Data, 1) => &mut caches.0,
+                            (CacheKind::Instruction, 1) => &mut caches.1,
+                            (CacheKind::Unified, 2) => &mut caches.2,
+                            (CacheKind::Unified, 3) => &mut caches.3,
+                            _ => continue,
+                        };
+                        if cache.properties.is_present() {
+                            cache_data.shared_by_cores = cache.properties.shared_by_cores;
+                        }
+                    }
+                }
+
+                caches
             }
-            Vendor::Intel => {
-                let mut l1_cache_data = PhysicalDeviceCacheProperties::default();
-                let mut l1_cache_instruction = PhysicalDeviceCacheProperties::default();
-                let mut l2_cache = PhysicalDeviceCacheProperties::default();
-                let mut l3_cache = PhysicalDeviceCacheProperties::default();
+            // Zhaoxin and VIA expose the deterministic cache parameters leaf like Intel.
+            Vendor::Intel | Vendor::Zhaoxin | Vendor::Via => {
+                let mut caches: [PhysicalDeviceCacheProperties; 6] = Default::default();
 
                 let mut i = 0;
-                loop {
-                    let cache = unsafe { std::arch::x86_64::__cpuid_count(4, i) };
-                    let ty = extract_bits(cache.eax, 0..5);
+                while let Some(cache) =
+                    cpuid(4, i).and_then(|result| parse_caches(4, i, &result).pop())
+                {
+                    i += 1;
+                    insert_cache(&mut caches, cache.kind, cache.level, cache.properties);
+                }
 
-                    if ty == 0 {
-                        break;
+                // Some (virtualized) CPUs only report the legacy descriptors of leaf `2`.
+                if i == 0 {
+                    for descriptor in crate::cpuid::leaf2_descriptors() {
+                        let (level, kind, properties) = match crate::cpuid::leaf2_cache(descriptor)
+                        {
+                            Some(cache) => cache,
+                            None => continue,
+                        };
+                        let slot = match (level, kind) {
+                            (1, CacheKind::Data) => 0,
+                            (1, CacheKind::Instruction) => 1,
+                            (1, CacheKind::Unified) => 5,
+                            (2, _) => 2,
+                            (3, _) => 3,
+                            _ => continue,
+                        };
+                        caches[slot] = properties;
                     }
+                }
 
-                    let level = extract_bits(cache.eax, 5..8);
-
-                    let line_size = extract_bits(cache.ebx, 0..12) + 1;
-                    let partitions = extract_bits(cache.ebx, 12..22) + 1;
-                    let associativity = extract_bits(cache.ebx, 22..32) + 1;
-                    let num_sets = cache.ecx + 1;
+                let [l1_cache_data, l1_cache_instruction, l2_cache, l3_cache, l4_cache, l1_cache_unified] =
+                    caches;
+                (
+                    l1_cache_data,
+                    l1_cache_instruction,
+                    l2_cache,
+                    l3_cache,
+                    l4_cache,
+                    l1_cache_unified,
+                )
+            }
+            _ => Default::default(),
+        }
+    }
+}
 
-                    let properties = PhysicalDeviceCacheProperties {
-                        size: line_size * partitions * associativity * num_sets,
-                        line_size,
-                    };
+#[cfg(feature = "std")]
+impl PhysicalDeviceProperties {
+    /// Query the properties of the current system.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the properties couldn't be retrieved, see `try_system`.
+    pub fn system() -> Self {
+        Self::try_system().unwrap()
+    }
 
-                    i += 1;
+    /// Query the properties of the current system.
+    ///
+    /// WebAssembly doesn't expose the hardware, only the number of logical cores is
+    /// reported via `navigator.hardwareConcurrency` with the `web` feature (otherwise `1`).
+    pub fn try_system() -> Result<Self, DeviceError> {
+        Self::try_system_with(DetectionBackend::Auto)
+    }
 
-                    let cache_data = match (ty, level) {
-                        (1, 1) => &mut l1_cache_data,
-                        (2, 1) => &mut l1_cache_instruction,
-                        (3, 2) => &mut l2_cache,
-                        (3, 3) => &mut l3_cache,
-                        _ => continue,
-                    };
+    /// Query the properties of the current system with the given detection backend.
+    ///
+    /// Returns `UnsupportedBackend` if the backend isn't available on the system.
+    pub fn try_system_with(backend: DetectionBackend) -> Result<Self, DeviceError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("system", ?backend).entered();
 
-                    *cache_data = properties;
+        let mut properties = match backend {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            DetectionBackend::Cpuid if !crate::cpuid::cpuid_available() => {
+                return Err(DeviceError::UnsupportedBackend)
+            }
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            DetectionBackend::Cpuid => Self::detect_cpuid()?,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            _ if !crate::cpuid::cpuid_available() => Self::detect_without_cpuid(),
+            #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+            DetectionBackend::Cpuid => return Err(DeviceError::UnsupportedBackend),
+            _ => Self::detect()?,
+        };
+        match backend {
+            #[cfg(feature = "hwloc")]
+            #[allow(unused_variables)]
+            DetectionBackend::Auto => {
+                if let Err(err) = properties.apply_hwloc() {
+                    debug!(%err, "hwloc topology unavailable");
                 }
-
-                (l1_cache_data, l1_cache_instruction, l2_cache, l3_cache)
             }
-            Vendor::Unknown => (
-                PhysicalDeviceCacheProperties::default(),
-                PhysicalDeviceCacheProperties::default(),
-                PhysicalDeviceCacheProperties::default(),
-                PhysicalDeviceCacheProperties::default(),
+            DetectionBackend::Sysfs => {
+                #[cfg(all(
+                    target_os = "linux",
+                    any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")
+                ))]
+                properties.apply_sysfs()?;
+                #[cfg(not(all(
+                    target_os = "linux",
+                    any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")
+                )))]
+                return Err(DeviceError::UnsupportedBackend);
+            }
+            DetectionBackend::Hwloc => {
+                #[cfg(feature = "hwloc")]
+                properties.apply_hwloc()?;
+                #[cfg(not(feature = "hwloc"))]
+                return Err(DeviceError::UnsupportedBackend);
+            }
+            _ => {}
+        }
+        properties.package_count = Self::system_package_count();
+        properties.recommended_prefetch_bytes = properties.default_prefetch_bytes();
+        #[allow(unused_variables)]
+        if let Some(translation) = properties.translation {
+            warn!(
+                %translation,
+                "running under binary translation, SIMD features may not match the hardware"
+            );
+        }
+        debug!(?properties, "detected properties");
+        Ok(properties)
+    }
+
+    /// Detect the properties from CPUID, only the core counts and memory are queried
+    /// from the OS.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn detect_cpuid() -> Result<Self, DeviceError> {
+        let properties = Self::from_cpuid();
+        let logical_cores = num_cpus::get();
+        let physical_cores = num_cpus::get_physical();
+        let threads_per_core =
+            Self::system_threads_per_core(properties.vendor, logical_cores, physical_cores);
+        let (total_memory_bytes, available_memory_bytes) = Self::system_memory();
+
+        Ok(PhysicalDeviceProperties {
+            logical_cores,
+            physical_cores,
+            threads_per_core,
+            total_memory_bytes,
+            available_memory_bytes,
+            numa_nodes: NumaNode::uniform(logical_cores),
+            ..properties
+        })
+    }
+
+    /// Detect the core counts, memory and caches from the OS alone, if CPUID can't be
+    /// executed.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn detect_without_cpuid() -> Self {
+        let logical_cores = num_cpus::get();
+        let physical_cores = num_cpus::get_physical().clamp(1, logical_cores.max(1));
+        let (total_memory_bytes, available_memory_bytes) = Self::system_memory();
+        #[cfg(not(target_os = "windows"))]
+        let numa_nodes = Self::system_numa_nodes(logical_cores);
+        #[cfg(target_os = "windows")]
+        let numa_nodes = NumaNode::uniform(logical_cores);
+        let core_clusters = vec![CoreCluster {
+            core_type: CoreType::Unknown,
+            logical_cores: (0..logical_cores).collect(),
+        }];
+
+        #[allow(unused_mut)]
+        let mut properties = PhysicalDeviceProperties::builder()
+            .logical_cores(logical_cores)
+            .physical_cores(physical_cores)
+            .threads_per_core((logical_cores / physical_cores).max(1))
+            .total_memory_bytes(total_memory_bytes)
+            .available_memory_bytes(available_memory_bytes)
+            .core_clusters(core_clusters)
+            .numa_nodes(numa_nodes)
+            .build();
+
+        #[cfg(target_os = "linux")]
+        if let Some(caches) = crate::sysfs::caches() {
+            properties.set_caches(caches);
+        }
+
+        properties
+    }
+
+    /// Replace the caches by the ones of sysfs, without falling back to CPUID.
+    ///
+    /// The inclusivity isn't exposed by sysfs and reported as unknown.
+    #[cfg(all(
+        target_os = "linux",
+        any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")
+    ))]
+    fn apply_sysfs(&mut self) -> Result<(), DeviceError> {
+        let caches = crate::sysfs::caches().ok_or(DeviceError::UnsupportedBackend)?;
+        self.set_caches(caches);
+        Ok(())
+    }
+
+    /// Replace all caches by the `(l1_data, l1_instruction, l2, l3, l4, l1_unified)` tuple.
+    #[cfg(all(
+        target_os = "linux",
+        any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")
+    ))]
+    fn set_caches(
+        &mut self,
+        (l1_cache_data, l1_cache_instruction, l2_cache, l3_cache, l4_cache, l1_cache_unified): (
+            PhysicalDeviceCacheProperties,
+            PhysicalDeviceCacheProperties,
+            PhysicalDeviceCacheProperties,
+            PhysicalDeviceCacheProperties,
+            PhysicalDeviceCacheProperties,
+            PhysicalDeviceCacheProperties,
+        ),
+    ) {
+        self.l1_cache_data = l1_cache_data;
+        self.l1_cache_instruction = l1_cache_instruction;
+        self.l1_cache_unified =
+            Some(l1_cache_unified).filter(PhysicalDeviceCacheProperties::is_present);
+        self.l2_cache = l2_cache;
+        self.l3_cache = l3_cache;
+        self.l4_cache = l4_cache;
+    }
+
+    /// Replace the cores, caches and NUMA nodes by the topology of hwloc.
+    ///
+    /// Keeps the natively detected values hwloc reports as unknown.
+    #[cfg(feature = "hwloc")]
+    fn apply_hwloc(&mut self) -> Result<(), DeviceError> {
+        let topology = crate::hwloc::topology()?;
+
+        if topology.logical_cores > 0 && topology.physical_cores > 0 {
+            self.logical_cores = topology.logical_cores;
+            self.physical_cores = topology.physical_cores;
+            self.threads_per_core = topology.logical_cores / topology.physical_cores;
+        }
+        for (cache, hwloc) in [
+            (&mut self.l1_cache_data, topology.l1_cache_data),
+            (
+                &mut self.l1_cache_instruction,
+                topology.l1_cache_instruction,
             ),
-        };
+            (&mut self.l2_cache, topology.l2_cache),
+            (&mut self.l3_cache, topology.l3_cache),
+            (&mut self.l4_cache, topology.l4_cache),
+        ] {
+            // hwloc doesn't expose the inclusivity and write policy.
+            if hwloc.is_present() {
+                *cache = PhysicalDeviceCacheProperties {
+                    inclusivity: cache.inclusivity,
+                    write_policy: cache.write_policy,
+                    ..hwloc
+                };
+            }
+        }
+        if !topology.numa_nodes.is_empty() {
+            self.numa_nodes = topology.numa_nodes;
+        }
+        Ok(())
+    }
 
-        PhysicalDeviceProperties {
-            vendor,
-            device,
-            logical_cores: num_cpus::get(),
-            physical_cores: num_cpus::get_physical(),
-            l1_cache_data,
-            l1_cache_instruction,
-            l2_cache,
-            l3_cache,
+    /// Query the properties of the current system, limited to the cores
+    /// the current process is allowed to use.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the properties couldn't be retrieved, see `try_system_constrained`.
+    pub fn system_constrained() -> Self {
+        Self::try_system_constrained().unwrap()
+    }
+
+    /// Query the properties of the current system, limited to the cores
+    /// the current process is allowed to use.
+    ///
+    /// On Linux the topology is restricted to the affinity mask (`sched_getaffinity`)
+    /// and the core counts are clamped to the cgroup v2 `cpu.max` quota.
+    /// Other platforms report the same properties as `try_system`.
+    pub fn try_system_constrained() -> Result<Self, DeviceError> {
+        Self::try_system_constrained_with(DetectionBackend::Auto)
+    }
+
+    /// Query the properties of the current system with the given detection backend,
+    /// limited to the cores the current process is allowed to use.
+    pub(crate) fn try_system_constrained_with(
+        backend: DetectionBackend,
+    ) -> Result<Self, DeviceError> {
+        let properties = Self::try_system_with(backend)?;
+
+        #[cfg(all(
+            target_os = "linux",
+            any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")
+        ))]
+        {
+            let mut properties = properties;
+            if let Some(cpus) = crate::sysfs::allowed_cpus().filter(|cpus| !cpus.is_empty()) {
+                let cores = crate::sysfs::core_affinity();
+                let physical_cores = if cores.is_empty() {
+                    (cpus.len() / properties.threads_per_core.max(1)).max(1)
+                } else {
+                    cores
+                        .iter()
+                        .filter(|core| core.logical_cores.iter().any(|cpu| cpus.contains(cpu)))
+                        .count()
+                };
+                properties = properties.restrict_to_package(&Package {
+                    logical_cores: cpus,
+                    physical_cores,
+                });
+            }
+            if let Some(quota) = crate::sysfs::cgroup_cpu_quota() {
+                properties.logical_cores = properties.logical_cores.min(quota);
+                properties.physical_cores = properties.physical_cores.min(quota);
+            }
+            Ok(properties)
+        }
+        #[cfg(not(all(
+            target_os = "linux",
+            any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")
+        )))]
+        {
+            Ok(properties)
         }
     }
-}
 
+    /// Query the base and maximum frequency in MHz.
+    ///
+    /// Sources are tried in order: CPUID leaf `0x16` (Intel), Linux `cpufreq` and
+    /// the frequency in the brand string. `0` if no stable source is available.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+    #[cfg_attr(
+        not(any(target_arch = "x86", target_arch = "x86_64")),
+        allow(unused_variables)
+    )]
+    fn system_frequency(vendor: Vendor) -> (u32, u32) {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        let (base_frequency, max_frequency) = Self::cpuid_frequency(vendor);
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+        let (base_frequency, max_frequency) = (0, 0);
+
+        #[cfg(target_os = "linux")]
+        let (base_frequency, max_frequency) = (
+            match base_frequency {
+                0 => crate::sysfs::cpufreq_khz("base_frequency").unwrap_or(0) / 1000,
+                frequency => frequency,
+            },
+            match max_frequency {
+                0 => crate::sysfs::cpufreq_khz("cpuinfo_max_freq").unwrap_or(0) / 1000,
+                frequency => frequency,
+            },
+        );
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        let base_frequency = match base_frequency {
+            0 => parse_brand_frequency(&Self::system_cpuid_brand()).unwrap_or(0),
+            frequency => frequency,
+        };
+
+        (base_frequency, max_frequency)
+    }
+
+    /// Total number of logical cores of the machine, across all packages.
+    ///
+    /// Cross-checked against the OS processor count, the larger number is returned
+    /// as the topology may undercount (e.g. processor groups on Windows).
+    pub fn total_logical_cores(&self) -> usize {
+        let os_cores = Self::system_online_cores();
+        if os_cores != self.logical_cores {
+            warn!(
+                topology = self.logical_cores,
+                os = os_cores,
+                "logical core count of the topology differs from the OS"
+            );
+        }
+        self.logical_cores.max(os_cores)
+    }
+
+    /// Total number of physical cores of the machine, across all packages.
+    ///
+    /// Cross-checked against the OS core count, the larger number is returned.
+    pub fn total_physical_cores(&self) -> usize {
+        let os_cores = Self::system_online_physical_cores();
+        if os_cores != self.physical_cores {
+            warn!(
+                topology = self.physical_cores,
+                os = os_cores,
+                "physical core count of the topology differs from the OS"
+            );
+        }
+        self.physical_cores.max(os_cores)
+    }
+
+    /// Stable hash of the hardware characteristics, e.g. as key for caches stored on disk.
+    ///
+    /// Covers the vendor, device, family, model, stepping, core and package counts and cache
+    /// geometry. The core counts of the whole machine are hashed, see `total_logical_cores`,
+    /// so the affinity mask and CPU quota of the process don't change the fingerprint.
+    /// Volatile values like the available memory aren't included.
+    /// The hash (FNV-1a) doesn't depend on the process, platform or Rust version.
+    pub fn fingerprint(&self) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0100_0000_01b3;
+
+        let mut hash = OFFSET_BASIS;
+        let mut write = |bytes: &[u8]| {
+            for &byte in bytes {
+                hash = (hash ^ byte as u64).wrapping_mul(PRIME);
+            }
+        };
+
+        // Strings are length prefixed to keep adjacent fields apart.
+        let vendor = self.vendor.to_string();
+        for string in [vendor.as_str(), self.device.as_str()] {
+            write(&(string.len() as u64).to_le_bytes());
+            write(string.as_bytes());
+        }
+        for value in [self.family, self.model, self.stepping] {
+            write(&value.to_le_bytes());
+        }
+        for value in [
+            self.total_logical_cores(),
+            self.total_physical_cores(),
+            self.package_count,
+        ] {
+            write(&(value as u64).to_le_bytes());
+        }
+        let l1_cache_unified = self.l1_cache_unified.as_ref().unwrap_or(&ABSENT_CACHE);
+        for cache in [
+            &self.l1_cache_data,
+            &self.l1_cache_instruction,
+            l1_cache_unified,
+            &self.l2_cache,
+            &self.l3_cache,
+            &self.l4_cache,
+        ] {
+            for value in [
+                cache.size,
+                cache.line_size,
+                cache.associativity,
+                cache.sets,
+                cache.shared_by_cores,
+            ] {
+                write(&value.to_le_bytes());
+            }
+        }
+
+        hash
+    }
+
+    /// Query the number of online logical cores of the machine.
+    ///
+    /// Not limited by the affinity mask or a CPU quota of the process.
+    fn system_online_cores() -> usize {
+        #[cfg(all(
+            target_os = "windows",
+            any(target_arch = "x86", target_arch = "x86_64")
+        ))]
+        return crate::windows::active_processor_count();
+
+        #[cfg(all(
+            target_os = "linux",
+            any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")
+        ))]
+        if let Some(cpus) = crate::sysfs::online_cpus() {
+            return cpus;
+        }
+
+        #[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+        {
+            let cpus = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_ONLN) };
+            if cpus > 0 {
+                return cpus as usize;
+            }
+        }
+
+        #[allow(unreachable_code)]
+        num_cpus::get()
+    }
+
+    /// Query the number of online physical cores of the machine.
+    ///
+    /// Not limited by the affinity mask or a CPU quota of the process.
+    fn system_online_physical_cores() -> usize {
+        // `num_cpus` falls back to the allowed cpus without `/proc/cpuinfo` core counts.
+        #[cfg(all(
+            target_os = "linux",
+            any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")
+        ))]
+        {
+            let cores = crate::sysfs::core_affinity().len();
+            if cores > 0 {
+                return cores;
+            }
+        }
+
+        num_cpus::get_physical()
+    }
+
+    /// Query the current frequency in MHz.
+    ///
+    /// Only available on Linux via `cpufreq` or `/proc/cpuinfo`.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+    fn system_current_frequency() -> u32 {
+        #[cfg(target_os = "linux")]
+        {
+            crate::sysfs::cpufreq_khz("scaling_cur_freq")
+                .map(|khz| khz / 1000)
+                .or_else(crate::sysfs::cpuinfo_frequency_mhz)
+                .unwrap_or(0)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            0
+        }
+    }
+
+    /// Query whether turbo boost is enabled.
+    ///
+    /// Only available on Linux via `sysfs` or the `IA32_MISC_ENABLE` MSR.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+    fn system_turbo_enabled() -> Option<bool> {
+        #[cfg(target_os = "linux")]
+        {
+            crate::sysfs::turbo_enabled()
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            None
+        }
+    }
+
+    /// Detect the binary translator the process is running in from the OS, falling back
+    /// to the CPUID signature.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn system_translation(cpuid: Option<Translation>) -> Option<Translation> {
+        #[cfg(target_os = "macos")]
+        if crate::sysctl::read_u64("sysctl.proc_translated") == Some(1) {
+            return Some(Translation::Rosetta2);
+        }
+        #[cfg(target_os = "windows")]
+        if crate::windows::is_emulated() {
+            return Some(Translation::Unknown);
+        }
+        cpuid
+    }
+
+    /// Query the thermal design power of a package in watts.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn system_tdp_watts() -> Option<u32> {
+        #[cfg(target_os = "linux")]
+        {
+            crate::sysfs::tdp_watts()
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            None
+        }
+    }
+
+    /// Re-query the volatile properties: available memory and current frequency.
+    ///
+    /// The remaining properties, like the topology, are left untouched.
+    pub fn refresh(&mut self) {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+        {
+            let (_, available_memory_bytes) = Self::system_memory();
+            self.available_memory_bytes = available_memory_bytes;
+            self.current_frequency_mhz = Self::system_current_frequency();
+        }
+    }
+
+    /// Query the number of hardware threads per physical core.
+    ///
+    /// On x86 the SMT width is read from the extended topology leaves,
+    /// but is only reported if the OS has SMT enabled.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+    #[allow(unused_variables)]
+    fn system_threads_per_core(
+        vendor: Vendor,
+        logical_cores: usize,
+        physical_cores: usize,
+    ) -> usize {
+        if logical_cores <= physical_cores {
+            return 1;
+        }
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        if let Some(threads_per_core) = Self::cpuid_threads_per_core(vendor) {
+            return threads_per_core;
+        }
+
+        logical_cores / physical_cores.max(1)
+    }
+
+    /// Query the core clusters of hybrid architectures.
+    ///
+    /// On x86 the core type is read from CPUID leaf `0x1A` on each logical core.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+    fn system_core_clusters(numa_nodes: &[NumaNode]) -> Vec<CoreCluster> {
+        let logical_cores = numa_nodes
+            .iter()
+            .flat_map(|node| node.logical_cores.iter().copied())
+            .collect::<Vec<_>>();
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            use crate::cpuid::cpuid;
+
+            let hybrid = cpuid(0x1A, 0).is_some()
+                && cpuid(7, 0).is_some_and(|leaf7| leaf7.edx & (1 << 15) != 0);
+            let core_types = if hybrid {
+                crate::affinity::on_each_cpu(&logical_cores, || {
+                    cpuid(0x1A, 0).map_or(0, |leaf| leaf.eax >> 24)
+                })
+            } else {
+                None
+            };
+
+            if let Some(core_types) = core_types {
+                let mut clusters = Vec::<CoreCluster>::new();
+                for (cpu, core_type) in core_types {
+                    let core_type = match core_type {
+                        0x20 => CoreType::Efficient,
+                        0x40 => CoreType::Performance,
+                        _ => CoreType::Unknown,
+                    };
+
+                    match clusters
+                        .iter_mut()
+                        .find(|cluster| cluster.core_type == core_type)
+                    {
+                        Some(cluster) => cluster.logical_cores.push(cpu),
+                        None => clusters.push(CoreCluster {
+                            core_type,
+                            logical_cores: vec![cpu],
+                        }),
+                    }
+                }
+                return clusters;
+            }
+        }
+
+        vec![CoreCluster {
+            core_type: CoreType::Unknown,
+            logical_cores,
+        }]
+    }
+
+    /// Query the core complexes of AMD CPUs.
+    ///
+    /// Logical cores are grouped by the L3 sharing of CPUID leaf `0x8000001D`
+    /// and the extended APIC ID of leaf `0x8000001E`, read on each logical core.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn system_core_complexes(vendor: Vendor, numa_nodes: &[NumaNode]) -> Vec<CoreComplex> {
+        use crate::cpuid::cpuid;
+
+        let topology_extensions = matches!(vendor, Vendor::AMD | Vendor::Hygon)
+            && cpuid(0x8000001E, 0).is_some()
+            && cpuid(0x80000001, 0).is_some_and(|cpuid| cpuid.ecx & (1 << 22) != 0);
+        if !topology_extensions {
+            return Vec::new();
+        }
+
+        let logical_cores = numa_nodes
+            .iter()
+            .flat_map(|node| node.logical_cores.iter().copied())
+            .collect::<Vec<_>>();
+        let slices = crate::affinity::on_each_cpu(&logical_cores, || {
+            let apic_id = cpuid(0x8000001E, 0)?.eax;
+            let l3_cache = (0..8)
+                .map_while(|i| {
+                    cpuid(0x8000001D, i)
+                        .and_then(|result| crate::cpuid::parse_caches(0x8000001D, i, &result).pop())
+                })
+                .find(|cache| cache.level == 3)?;
+            Some(L3Slice {
+                apic_id,
+                shared_by_cores: l3_cache.properties.shared_by_cores,
+                size: l3_cache.properties.size,
+            })
+        });
+
+        let slices = slices
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(cpu, slice)| Some((cpu, slice?)))
+            .collect::<Vec<_>>();
+        group_core_complexes(&slices)
+    }
+
+    /// Query the total and available physical memory in bytes.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+    fn system_memory() -> (u64, u64) {
+        #[cfg(all(
+            target_os = "windows",
+            any(target_arch = "x86", target_arch = "x86_64")
+        ))]
+        {
+            crate::windows::memory().unwrap_or((0, 0))
+        }
+        #[cfg(target_os = "linux")]
+        {
+            crate::sysfs::memory().unwrap_or((0, 0))
+        }
+        #[cfg(target_os = "macos")]
+        {
+            use crate::sysctl;
+
+            let total = sysctl::read_u64("hw.memsize").unwrap_or(0);
+            let page_size = sysctl::read_u64("hw.pagesize").unwrap_or(0);
+
+            // Inactive and purgeable pages can be reclaimed without swapping.
+            let mut info = unsafe { std::mem::zeroed::<libc::vm_statistics64>() };
+            let mut count = libc::HOST_VM_INFO64_COUNT;
+            #[allow(deprecated)]
+            let result = unsafe {
+                libc::host_statistics64(
+                    libc::mach_host_self(),
+                    libc::HOST_VM_INFO64,
+                    &mut info as *mut _ as libc::host_info64_t,
+                    &mut count,
+                )
+            };
+            let available_pages = if result == libc::KERN_SUCCESS {
+                info.free_count as u64 + info.inactive_count as u64 + info.purgeable_count as u64
+            } else {
+                sysctl::read_u64("vm.page_free_count").unwrap_or(0)
+            };
+            (total, available_pages * page_size)
+        }
+        #[cfg(target_os = "freebsd")]
+        {
+            use crate::sysctl;
+
+            let total = sysctl::read_u64("hw.physmem").unwrap_or(0);
+            let page_size = sysctl::read_u64("hw.pagesize").unwrap_or(0);
+            // Inactive and cached pages can be reclaimed without swapping,
+            // the cache queue was removed in FreeBSD 12.
+            let available_pages = [
+                "vm.stats.vm.v_free_count",
+                "vm.stats.vm.v_inactive_count",
+                "vm.stats.vm.v_cache_count",
+            ]
+            .iter()
+            .map(|name| sysctl::read_u64(name).unwrap_or(0))
+            .sum::<u64>();
+            (total, available_pages * page_size)
+        }
+        #[cfg(not(any(
+            all(
+                target_os = "windows",
+                any(target_arch = "x86", target_arch = "x86_64")
+            ),
+            target_os = "linux",
+            target_os = "macos",
+            target_os = "freebsd"
+        )))]
+        {
+            (0, 0)
+        }
+    }
+
+    #[cfg(all(
+        not(target_os = "windows"),
+        any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")
+    ))]
+    fn system_numa_nodes(logical_cores: usize) -> Vec<NumaNode> {
+        #[cfg(target_os = "linux")]
+        let numa_nodes = crate::sysfs::numa_nodes();
+        #[cfg(not(target_os = "linux"))]
+        let numa_nodes = Vec::new();
+
+        if numa_nodes.is_empty() {
+            NumaNode::uniform(logical_cores)
+        } else {
+            numa_nodes
+        }
+    }
+
+    /// Query the logical cores of each physical core of this device.
+    ///
+    /// Only cores whose logical cores belong to this device's NUMA nodes are returned.
+    /// Returns an empty list if the core topology isn't exposed.
+    pub fn core_affinity(&self) -> Vec<CoreAffinity> {
+        #[cfg(all(
+            target_os = "windows",
+            any(target_arch = "x86", target_arch = "x86_64")
+        ))]
+        let cores = crate::windows::logical_processor_information()
+            .map(|infos| crate::windows::ProcessorInformation::parse(&infos).cores)
+            .unwrap_or_default();
+        #[cfg(all(
+            target_os = "linux",
+            any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")
+        ))]
+        let cores = crate::sysfs::core_affinity();
+        #[cfg(not(any(
+            all(
+                target_os = "windows",
+                any(target_arch = "x86", target_arch = "x86_64")
+            ),
+            all(
+                target_os = "linux",
+                any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")
+            )
+        )))]
+        let cores = Vec::<CoreAffinity>::new();
+
+        let contains = |cpu: &usize| {
+            self.numa_nodes
+                .iter()
+                .any(|node| node.logical_cores.contains(cpu))
+        };
+        cores
+            .into_iter()
+            .filter(|core| core.logical_cores.iter().all(contains))
+            .collect()
+    }
+
+    /// Query the SMT siblings of each physical core of this device.
+    ///
+    /// Maps the index of the physical core to the OS indices of its logical cores,
+    /// ordered by the first logical core. Uses the topology of the OS, on x86 falls
+    /// back to the x2APIC topology of CPUID leaf `0x1F` (or `0xB`).
+    pub fn smt_siblings(&self) -> Vec<(usize, Vec<usize>)> {
+        #[allow(unused_mut)]
+        let mut cores = self.core_affinity();
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        if cores.is_empty() {
+            cores = self.cpuid_core_affinity();
+        }
+        cores.sort();
+
+        cores
+            .into_iter()
+            .enumerate()
+            .map(|(index, core)| (index, core.logical_cores))
+            .collect()
+    }
+
+    /// Query the APIC ID of each logical core of this device, ordered by OS index.
+    ///
+    /// Reads the x2APIC ID of CPUID leaf `0x1F` (or `0xB`), falling back to the initial
+    /// APIC ID of leaf `1`, on each logical core. Logical cores the thread couldn't be
+    /// pinned to are skipped. Empty on non-x86 devices.
+    pub fn apic_ids(&self) -> Vec<u32> {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            use crate::cpuid::cpuid;
+
+            if !crate::cpuid::cpuid_available() {
+                return Vec::new();
+            }
+            let mut logical_cores = self
+                .numa_nodes
+                .iter()
+                .flat_map(|node| node.logical_cores.iter().copied())
+                .collect::<Vec<_>>();
+            logical_cores.sort_unstable();
+
+            let leaf = x2apic_leaf();
+            let ids = crate::affinity::on_each_cpu(&logical_cores, || match leaf {
+                Some(leaf) => cpuid(leaf, 0).map_or(0, |topology| topology.edx),
+                None => cpuid(1, 0).map_or(0, |leaf1| leaf1.ebx >> 24),
+            });
+            ids.unwrap_or_default()
+                .into_iter()
+                .map(|(_, id)| id)
+                .collect()
+        }
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+        {
+            Vec::new()
+        }
+    }
+
+    /// Group the logical cores of this device by the x2APIC ID bits above the SMT level.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn cpuid_core_affinity(&self) -> Vec<CoreAffinity> {
+        let leaf = match x2apic_leaf() {
+            Some(leaf) => leaf,
+            None => return Vec::new(),
+        };
+
+        let logical_cores = self
+            .numa_nodes
+            .iter()
+            .flat_map(|node| node.logical_cores.iter().copied())
+            .collect::<Vec<_>>();
+        let ids = crate::affinity::on_each_cpu(&logical_cores, || {
+            let smt = crate::cpuid::cpuid(leaf, 0)?;
+            // Subleaf `0` describes the SMT level (type `1`) if present.
+            let smt_shift = if (smt.ecx >> 8) & 0xFF == 1 {
+                smt.eax & 0x1F
+            } else {
+                0
+            };
+            Some((smt.edx, smt_shift))
+        });
+
+        let ids = ids
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(cpu, id)| Some((cpu, id?)))
+            .collect::<Vec<_>>();
+        group_smt_siblings(&ids)
+    }
+
+    /// Query the topology tree of this device's NUMA nodes.
+    ///
+    /// Without per-instance cache information, the L1 and L2 caches are attached
+    /// to each core and the L3 cache to each package.
+    pub(crate) fn topology(&self) -> Topology {
+        let cores = self.core_affinity();
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+        let packages = Self::system_packages()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|package| package.logical_cores)
+            .collect::<Vec<_>>();
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+        let packages = Vec::new();
+
+        #[cfg(all(
+            target_os = "windows",
+            any(target_arch = "x86", target_arch = "x86_64")
+        ))]
+        let caches = crate::windows::logical_processor_information()
+            .map(|infos| crate::windows::ProcessorInformation::parse(&infos).caches)
+            .unwrap_or_default();
+        #[cfg(all(
+            target_os = "linux",
+            any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")
+        ))]
+        let caches = crate::sysfs::cache_instances();
+        #[cfg(not(any(
+            all(
+                target_os = "windows",
+                any(target_arch = "x86", target_arch = "x86_64")
+            ),
+            all(
+                target_os = "linux",
+                any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")
+            )
+        )))]
+        let caches = Vec::new();
+
+        let mut topology = Topology::build(packages, &self.numa_nodes, cores, caches);
+        if topology.cores().all(|core| core.caches.is_empty()) {
+            self.attach_uniform_caches(&mut topology);
+        }
+        topology
+    }
+
+    /// Attach the device wide caches to each core and package of the topology.
+    fn attach_uniform_caches(&self, topology: &mut Topology) {
+        let l1_cache_unified = self.l1_cache_unified.as_ref().unwrap_or(&ABSENT_CACHE);
+        let instance = |level, kind, properties: &PhysicalDeviceCacheProperties, cpus: &[usize]| {
+            CacheInstance {
+                level,
+                kind,
+                properties: properties.clone(),
+                logical_cores: cpus.to_vec(),
+            }
+        };
+
+        for package in &mut topology.packages {
+            for node in &mut package.numa_nodes {
+                for core in &mut node.cores {
+                    let private = [
+                        (1, CacheKind::Data, &self.l1_cache_data),
+                        (1, CacheKind::Instruction, &self.l1_cache_instruction),
+                        (1, CacheKind::Unified, l1_cache_unified),
+                        (2, CacheKind::Unified, &self.l2_cache),
+                    ];
+                    for (level, kind, properties) in private {
+                        if properties.is_present() {
+                            core.caches.push(instance(
+                                level,
+                                kind,
+                                properties,
+                                &core.logical_cores,
+                            ));
+                        }
+                    }
+                }
+            }
+            if self.l3_cache.is_present() && package.caches.is_empty() {
+                package.caches.push(instance(
+                    3,
+                    CacheKind::Unified,
+                    &self.l3_cache,
+                    &package.logical_cores,
+                ));
+            }
+        }
+    }
+
+    /// Query the physical packages of the current system.
+    ///
+    /// Returns an empty list if the package topology isn't exposed.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+    fn system_packages() -> Result<Vec<Package>, DeviceError> {
+        #[cfg(all(
+            target_os = "windows",
+            any(target_arch = "x86", target_arch = "x86_64")
+        ))]
+        {
+            let infos = crate::windows::logical_processor_information()?;
+            Ok(crate::windows::ProcessorInformation::parse(&infos).packages)
+        }
+        #[cfg(target_os = "linux")]
+        {
+            Ok(crate::sysfs::packages())
+        }
+        #[cfg(not(any(
+            all(
+                target_os = "windows",
+                any(target_arch = "x86", target_arch = "x86_64")
+            ),
+            target_os = "linux"
+        )))]
+        {
+            Ok(Vec::new())
+        }
+    }
+
+    /// Query the number of physical packages of the current system.
+    ///
+    /// Returns `1` if the package topology isn't exposed.
+    fn system_package_count() -> usize {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+        match Self::system_packages() {
+            Ok(packages) => packages.len().max(1),
+            Err(_) => 1,
+        }
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+        1
+    }
+
+    /// Query the properties of each physical package of the current system.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+    pub(crate) fn try_system_packages() -> Result<Vec<Self>, DeviceError> {
+        let properties = Self::try_system()?;
+        let packages = Self::system_packages()?;
+
+        if packages.len() <= 1 {
+            return Ok(vec![properties]);
+        }
+
+        Ok(packages
+            .iter()
+            .map(|package| {
+                #[allow(unused_mut)]
+                let mut package_properties = PhysicalDeviceProperties {
+                    package_count: 1,
+                    ..properties.restrict_to_package(package)
+                };
+                #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+                package_properties.identify_package(package);
+                package_properties
+            })
+            .collect())
+    }
+
+    /// Query the vendor and device of a package from CPUID on one of its cores.
+    ///
+    /// Keeps the current values if the thread couldn't be pinned to the package.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn identify_package(&mut self, package: &Package) {
+        if !crate::cpuid::cpuid_available() {
+            return;
+        }
+        let cpu = match package.logical_cores.first() {
+            Some(&cpu) => cpu,
+            None => return,
+        };
+        let identity = crate::affinity::on_each_cpu(&[cpu], || {
+            let (vendor, device) = Self::system_cpuid_vendor_device();
+            let leaf1_eax = crate::cpuid::cpuid(1, 0).map_or(0, |leaf1| leaf1.eax);
+            (vendor, device, cpuid_family_model_stepping(leaf1_eax))
+        })
+        .and_then(|results| results.into_iter().next());
+
+        if let Some((_, (vendor, device, (family, model, stepping)))) = identity {
+            self.vendor = vendor;
+            self.device = device;
+            self.family = family;
+            self.model = model;
+            self.stepping = stepping;
+            self.microarchitecture = microarchitecture(vendor, family, model).map(String::from);
+        }
+    }
+
+    /// Query the properties of each physical package of the current system.
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    pub(crate) fn try_system_packages() -> Result<Vec<Self>, DeviceError> {
+        Ok(vec![Self::try_system()?])
+    }
+
+    /// Restrict the core topology to the cores of a single package.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+    fn restrict_to_package(&self, package: &Package) -> Self {
+        let contains = |cpu: &usize| package.logical_cores.contains(cpu);
+
+        let numa_nodes = self
+            .numa_nodes
+            .iter()
+            .map(|node| NumaNode {
+                node_id: node.node_id,
+                logical_cores: node
+                    .logical_cores
+                    .iter()
+                    .copied()
+                    .filter(contains)
+                    .collect(),
+            })
+            .filter(|node| !node.logical_cores.is_empty())
+            .collect();
+        let core_clusters = self
+            .core_clusters
+            .iter()
+            .map(|cluster| CoreCluster {
+                core_type: cluster.core_type,
+                logical_cores: cluster
+                    .logical_cores
+                    .iter()
+                    .copied()
+                    .filter(contains)
+                    .collect(),
+            })
+            .filter(|cluster| !cluster.logical_cores.is_empty())
+            .collect();
+
+        let core_complexes = self
+            .core_complexes
+            .iter()
+            .filter(|complex| complex.logical_cores.iter().any(contains))
+            .cloned()
+            .collect();
+
+        PhysicalDeviceProperties {
+            logical_cores: package.logical_cores.len(),
+            physical_cores: package.physical_cores,
+            numa_nodes,
+            core_clusters,
+            core_complexes,
+            ..self.clone()
+        }
+    }
+
+    /// Detect the properties of the current system.
+    #[cfg(all(
+        target_os = "windows",
+        any(target_arch = "x86", target_arch = "x86_64")
+    ))]
+    fn detect() -> Result<Self, DeviceError> {
+        use crate::windows::{self, ProcessorInformation};
+
+        let infos = windows::logical_processor_information()?;
+        let ProcessorInformation {
+            logical_cores,
+            physical_cores,
+            l1_cache_data,
+            l1_cache_instruction,
+            l1_cache_unified,
+            l2_cache,
+            l3_cache,
+            l4_cache,
+            numa_nodes,
+            ..
+        } = ProcessorInformation::parse(&infos);
+        let numa_nodes = if numa_nodes.is_empty() {
+            NumaNode::uniform(logical_cores)
+        } else {
+            numa_nodes
+        };
+        let core_clusters = Self::system_core_clusters(&numa_nodes);
+
+        let properties = Self::from_cpuid();
+        let vendor = properties.vendor;
+        let core_complexes = Self::system_core_complexes(vendor, &numa_nodes);
+        let (base_frequency_mhz, max_frequency_mhz) = Self::system_frequency(vendor);
+        let current_frequency_mhz = Self::system_current_frequency();
+        let turbo_enabled = Self::system_turbo_enabled();
+        let tdp_watts = Self::system_tdp_watts();
+        let (total_memory_bytes, available_memory_bytes) = Self::system_memory();
+        let threads_per_core = Self::system_threads_per_core(vendor, logical_cores, physical_cores);
+        let translation = Self::system_translation(properties.translation);
+
+        // The logical processor information doesn't expose the inclusivity.
+        let with_inclusivity =
+            |cache: PhysicalDeviceCacheProperties, cpuid: &PhysicalDeviceCacheProperties| {
+                PhysicalDeviceCacheProperties {
+                    inclusivity: cpuid.inclusivity,
+                    ..cache
+                }
+            };
+
+        Ok(PhysicalDeviceProperties {
+            logical_cores,
+            physical_cores,
+            threads_per_core,
+            l1_cache_data,
+            l1_cache_instruction,
+            l1_cache_unified: Some(l1_cache_unified)
+                .filter(PhysicalDeviceCacheProperties::is_present),
+            l2_cache: with_inclusivity(l2_cache, &properties.l2_cache),
+            l3_cache: with_inclusivity(l3_cache, &properties.l3_cache),
+            l4_cache: with_inclusivity(l4_cache, &properties.l4_cache),
+            base_frequency_mhz,
+            max_frequency_mhz,
+            current_frequency_mhz,
+            turbo_enabled,
+            tdp_watts,
+            total_memory_bytes,
+            available_memory_bytes,
+            core_clusters,
+            core_complexes,
+            numa_nodes,
+            translation,
+            ..properties
+        })
+    }
+
+    /// Detect the properties of the current system.
+    #[cfg(all(
+        not(target_os = "windows"),
+        any(target_arch = "x86", target_arch = "x86_64")
+    ))]
+    fn detect() -> Result<Self, DeviceError> {
+        #[allow(unused_mut)]
+        let mut properties = Self::from_cpuid();
+        let vendor = properties.vendor;
+        let (base_frequency_mhz, max_frequency_mhz) = Self::system_frequency(vendor);
+        let current_frequency_mhz = Self::system_current_frequency();
+        let turbo_enabled = Self::system_turbo_enabled();
+        let tdp_watts = Self::system_tdp_watts();
+        let (total_memory_bytes, available_memory_bytes) = Self::system_memory();
+
+        #[cfg(target_os = "freebsd")]
+        let (logical_cores, physical_cores) = {
+            use crate::sysctl;
+
+            // Fill in line sizes the CPUID leaves didn't report.
+            let line_size = sysctl::read_u64("hw.cacheline").unwrap_or(0) as u32;
+            for cache in [
+                &mut properties.l1_cache_data,
+                &mut properties.l1_cache_instruction,
+                &mut properties.l2_cache,
+                &mut properties.l3_cache,
+                &mut properties.l4_cache,
+            ] {
+                if cache.is_present() && cache.line_size == 0 {
+                    cache.line_size = line_size;
+                }
+            }
+
+            (
+                sysctl::read_u64("hw.ncpu").map_or_else(num_cpus::get, |ncpu| ncpu as usize),
+                sysctl::read_u64("kern.smp.cores")
+                    .map_or_else(num_cpus::get_physical, |cores| cores as usize),
+            )
+        };
+        #[cfg(target_os = "macos")]
+        let (logical_cores, physical_cores) = {
+            use crate::sysctl;
+
+            // The OS values are more reliable than CPUID under Rosetta or VMs.
+            let line_size = sysctl::read_u64("hw.cachelinesize").unwrap_or(0) as u32;
+            for (name, cache) in [
+                ("hw.l1dcachesize", &mut properties.l1_cache_data),
+                ("hw.l1icachesize", &mut properties.l1_cache_instruction),
+                ("hw.l2cachesize", &mut properties.l2_cache),
+                ("hw.l3cachesize", &mut properties.l3_cache),
+            ] {
+                let size = sysctl::read_u64(name).unwrap_or(0) as u32;
+                if size == 0 {
+                    continue;
+                }
+                if line_size > 0 {
+                    cache.line_size = line_size;
+                }
+                if size != cache.size {
+                    cache.size = size;
+                    cache.sets = size
+                        .checked_div(cache.associativity * cache.line_size)
+                        .unwrap_or(0);
+                }
+            }
+
+            (
+                sysctl::read_u64("hw.logicalcpu").map_or_else(num_cpus::get, |cpus| cpus as usize),
+                sysctl::read_u64("hw.physicalcpu")
+                    .map_or_else(num_cpus::get_physical, |cpus| cpus as usize),
+            )
+        };
+        #[cfg(not(any(target_os = "freebsd", target_os = "macos")))]
+        let (logical_cores, physical_cores) = (num_cpus::get(), num_cpus::get_physical());
+
+        // Prefer the caches reported by the kernel, CPUID may be masked in containers.
+        #[cfg(target_os = "linux")]
+        if let Some((
+            l1_cache_data,
+            l1_cache_instruction,
+            l2_cache,
+            l3_cache,
+            l4_cache,
+            l1_cache_unified,
+        )) = crate::sysfs::caches()
+        {
+            if l1_cache_unified.is_present() {
+                properties.l1_cache_unified = Some(l1_cache_unified);
+            }
+            for (cache, sysfs) in [
+                (&mut properties.l1_cache_data, l1_cache_data),
+                (&mut properties.l1_cache_instruction, l1_cache_instruction),
+                (&mut properties.l2_cache, l2_cache),
+                (&mut properties.l3_cache, l3_cache),
+                (&mut properties.l4_cache, l4_cache),
+            ] {
+                // The inclusivity isn't exposed by sysfs.
+                if sysfs.is_present() {
+                    *cache = PhysicalDeviceCacheProperties {
+                        inclusivity: cache.inclusivity,
+                        ..sysfs
+                    };
+                }
+            }
+        }
+
+        // Only report page sizes the kernel has enabled.
+        #[cfg(target_os = "linux")]
+        if let Some(kernel_sizes) = crate::sysfs::huge_page_sizes() {
+            properties
+                .large_page_sizes
+                .retain(|size| kernel_sizes.contains(size));
+        }
+
+        // The kernel also derives the invariant TSC from the family on older CPUs.
+        #[cfg(target_os = "linux")]
+        if let Some(flags) = crate::sysfs::cpuinfo_flags() {
+            let has = |flag: &str| flags.iter().any(|f| f == flag);
+            let kernel_invariant_tsc = has("constant_tsc") && has("nonstop_tsc");
+            if kernel_invariant_tsc != properties.invariant_tsc {
+                debug!(
+                    cpuid = properties.invariant_tsc,
+                    kernel = kernel_invariant_tsc,
+                    "invariant TSC of CPUID differs from the kernel"
+                );
+            }
+            properties.invariant_tsc |= kernel_invariant_tsc;
+        }
+
+        let threads_per_core = Self::system_threads_per_core(vendor, logical_cores, physical_cores);
+        let numa_nodes = Self::system_numa_nodes(logical_cores);
+        let core_clusters = Self::system_core_clusters(&numa_nodes);
+        let core_complexes = Self::system_core_complexes(vendor, &numa_nodes);
+        let translation = Self::system_translation(properties.translation);
+
+        Ok(PhysicalDeviceProperties {
+            logical_cores,
+            physical_cores,
+            threads_per_core,
+            base_frequency_mhz,
+            max_frequency_mhz,
+            current_frequency_mhz,
+            turbo_enabled,
+            tdp_watts,
+            total_memory_bytes,
+            available_memory_bytes,
+            core_clusters,
+            core_complexes,
+            numa_nodes,
+            translation,
+            ..properties
+        })
+    }
+
+    /// Detect the properties of the current system.
+    #[cfg(target_arch = "aarch64")]
+    fn detect() -> Result<Self, DeviceError> {
+        #[cfg(target_os = "macos")]
+        let (
+            vendor,
+            device,
+            (l1_cache_data, l1_cache_instruction, l2_cache, l3_cache, l4_cache, l1_cache_unified),
+        ) = {
+            use crate::sysctl;
+
+            let line_size = sysctl::read_u64("hw.cachelinesize").unwrap_or(0) as u32;
+            let cache = |name: &str, shared_by_cores: u32| {
+                let size = sysctl::read_u64(name).unwrap_or(0) as u32;
+                PhysicalDeviceCacheProperties {
+                    size,
+                    line_size: if size > 0 { line_size } else { 0 },
+                    shared_by_cores: if size > 0 { shared_by_cores } else { 0 },
+                    ..Default::default()
+                }
+            };
+            // L1 caches are private, the L2 is shared by a cluster of cores.
+            let cpus_per_l2 = sysctl::read_u64("hw.perflevel0.cpusperl2").unwrap_or(0) as u32;
+
+            (
+                Vendor::Apple,
+                sysctl::read_string("machdep.cpu.brand_string").unwrap_or_default(),
+                (
+                    cache("hw.l1dcachesize", 1),
+                    cache("hw.l1icachesize", 1),
+                    cache("hw.l2cachesize", cpus_per_l2),
+                    cache("hw.l3cachesize", 0),
+                    Default::default(),
+                    Default::default(),
+                ),
+            )
+        };
+
+        #[cfg(not(target_os = "macos"))]
+        let (
+            vendor,
+            device,
+            (l1_cache_data, l1_cache_instruction, l2_cache, l3_cache, l4_cache, l1_cache_unified),
+        ) = {
+            #[cfg(target_os = "linux")]
+            let caches = crate::sysfs::caches().unwrap_or_default();
+            #[cfg(not(target_os = "linux"))]
+            let caches = Default::default();
+
+            (Vendor::Arm, String::new(), caches)
+        };
+
+        let (base_frequency_mhz, max_frequency_mhz) = Self::system_frequency(vendor);
+        let current_frequency_mhz = Self::system_current_frequency();
+        let turbo_enabled = Self::system_turbo_enabled();
+        let (total_memory_bytes, available_memory_bytes) = Self::system_memory();
+        #[cfg(target_os = "linux")]
+        let large_page_sizes = crate::sysfs::huge_page_sizes().unwrap_or_default();
+        #[cfg(not(target_os = "linux"))]
+        let large_page_sizes = Vec::new();
+        let logical_cores = num_cpus::get();
+        let physical_cores = num_cpus::get_physical();
+        let threads_per_core = Self::system_threads_per_core(vendor, logical_cores, physical_cores);
+        let numa_nodes = Self::system_numa_nodes(logical_cores);
+        let core_clusters = Self::system_core_clusters(&numa_nodes);
+
+        Ok(PhysicalDeviceProperties {
+            vendor,
+            device,
+            family: 0,
+            model: 0,
+            stepping: 0,
+            microarchitecture: None,
+            logical_cores,
+            physical_cores,
+            threads_per_core,
+            package_count: 1,
+            l1_cache_data,
+            l1_cache_instruction,
+            l1_cache_unified: Some(l1_cache_unified)
+                .filter(PhysicalDeviceCacheProperties::is_present),
+            l2_cache,
+            l3_cache,
+            l4_cache,
+            recommended_prefetch_bytes: 0,
+            l1_tlb_data: Default::default(),
+            l1_tlb_instruction: Default::default(),
+            l2_tlb: Default::default(),
+            base_frequency_mhz,
+            max_frequency_mhz,
+            current_frequency_mhz,
+            turbo_enabled,
+            invariant_tsc: false,
+            tdp_watts: None,
+            total_memory_bytes,
+            available_memory_bytes,
+            large_page_sizes,
+            core_clusters,
+            core_complexes: Vec::new(),
+            numa_nodes,
+            hypervisor: None,
+            translation: None,
+            security_features: SecurityFeatures::default(),
+            pmu_counters: PmuInfo::default(),
+            endianness: Endianness::native(),
+            pointer_width_bits: usize::BITS as u8,
+        })
+    }
+
+    /// Detect the properties of the current system.
+    #[cfg(target_family = "wasm")]
+    fn detect() -> Result<Self, DeviceError> {
+        #[cfg(feature = "web")]
+        let logical_cores = web_sys::window()
+            .map(|window| window.navigator().hardware_concurrency() as usize)
+            .filter(|&cores| cores > 0)
+            .unwrap_or(1);
+        #[cfg(not(feature = "web"))]
+        let logical_cores = 1;
+
+        let numa_nodes = NumaNode::uniform(logical_cores);
+        let core_clusters = vec![CoreCluster {
+            core_type: CoreType::Unknown,
+            logical_cores: numa_nodes[0].logical_cores.clone(),
+        }];
+
+        Ok(PhysicalDeviceProperties::builder()
+            .logical_cores(logical_cores)
+            .physical_cores(logical_cores)
+            .threads_per_core(1)
+            .core_clusters(core_clusters)
+            .numa_nodes(numa_nodes)
+            .build())
+    }
+
+    /// Detect the properties of the current system.
+    #[cfg(not(any(
+        target_arch = "x86",
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_family = "wasm"
+    )))]
+    fn detect() -> Result<Self, DeviceError> {
+        Err(DeviceError::UnsupportedArchitecture)
+    }
+}
+
+impl PhysicalDeviceProperties {
+    /// Returns if the physical cores run multiple hardware threads.
+    pub fn is_hyperthreaded(&self) -> bool {
+        self.threads_per_core > 1
+    }
+
+    /// Returns if all memory is uniformly accessible (UMA), i.e. there's a single NUMA node.
+    ///
+    /// Also `true` if no NUMA nodes are known, e.g. for properties only derived from CPUID.
+    pub fn is_uniform_memory(&self) -> bool {
+        self.numa_nodes.len() <= 1
+    }
+
+    /// Returns if both properties describe the same device, allowing numeric values
+    /// to differ by `tolerance_pct` percent of the larger value.
+    ///
+    /// Compares the vendor, device, family, model and stepping exactly and the core
+    /// counts, caches, TLBs, frequencies, TDP and total memory within the tolerance.
+    /// The volatile values updated by `refresh` and the topology aren't compared.
+    pub fn approx_eq(&self, other: &PhysicalDeviceProperties, tolerance_pct: f32) -> bool {
+        let tolerance = tolerance_pct.max(0.0) as f64 / 100.0;
+        let close = |a: u64, b: u64| (a.max(b) - a.min(b)) as f64 <= a.max(b) as f64 * tolerance;
+        let caches_close = |a: &PhysicalDeviceCacheProperties,
+                            b: &PhysicalDeviceCacheProperties| {
+            close(a.size as u64, b.size as u64)
+                && close(a.line_size as u64, b.line_size as u64)
+                && close(a.associativity as u64, b.associativity as u64)
+                && close(a.sets as u64, b.sets as u64)
+                && close(a.shared_by_cores as u64, b.shared_by_cores as u64)
+        };
+        let tlbs_close = |a: &PhysicalDeviceTlbProperties, b: &PhysicalDeviceTlbProperties| {
+            close(a.entries as u64, b.entries as u64)
+                && close(a.page_size_bytes as u64, b.page_size_bytes as u64)
+        };
+
+        self.vendor == other.vendor
+            && self.device == other.device
+            && (self.family, self.model, self.stepping)
+                == (other.family, other.model, other.stepping)
+            && close(self.logical_cores as u64, other.logical_cores as u64)
+            && close(self.physical_cores as u64, other.physical_cores as u64)
+            && close(self.threads_per_core as u64, other.threads_per_core as u64)
+            && caches_close(&self.l1_cache_data, &other.l1_cache_data)
+            && caches_close(&self.l1_cache_instruction, &other.l1_cache_instruction)
+            && match (&self.l1_cache_unified, &other.l1_cache_unified) {
+                (Some(a), Some(b)) => caches_close(a, b),
+                (a, b) => a.is_none() && b.is_none(),
+            }
+            && caches_close(&self.l2_cache, &other.l2_cache)
+            && caches_close(&self.l3_cache, &other.l3_cache)
+            && caches_close(&self.l4_cache, &other.l4_cache)
+            && tlbs_close(&self.l1_tlb_data, &other.l1_tlb_data)
+            && tlbs_close(&self.l1_tlb_instruction, &other.l1_tlb_instruction)
+            && tlbs_close(&self.l2_tlb, &other.l2_tlb)
+            && close(
+                self.base_frequency_mhz as u64,
+                other.base_frequency_mhz as u64,
+            )
+            && close(
+                self.max_frequency_mhz as u64,
+                other.max_frequency_mhz as u64,
+            )
+            && close(
+                self.tdp_watts.unwrap_or(0) as u64,
+                other.tdp_watts.unwrap_or(0) as u64,
+            )
+            && close(self.total_memory_bytes, other.total_memory_bytes)
+    }
+
+    /// Best-effort name of the model.
+    ///
+    /// The device name if known, otherwise the vendor with family and model.
+    pub fn approximate_model(&self) -> String {
+        let device = self.device.trim();
+        if !device.is_empty() {
+            String::from(device)
+        } else if self.family == 0 && self.model == 0 {
+            self.vendor.to_string()
+        } else {
+            format!(
+                "{} family {:#x} model {:#x}",
+                self.vendor, self.family, self.model
+            )
+        }
+    }
+
+    /// Coarse frequency penalty of heavy AVX-512 use, looked up by microarchitecture.
+    ///
+    /// A heuristic table, not measured: Skylake server derivates throttle heavily,
+    /// Ice Lake to Rocket Lake lightly, Sapphire Rapids and AMD Zen 4+ not at all.
+    /// `Unknown` for unrecognized parts or parts without AVX-512.
+    pub fn avx512_frequency_penalty(&self) -> Penalty {
+        match (self.vendor, self.family, self.model) {
+            // Skylake-SP, Cascade Lake, Cooper Lake.
+            (Vendor::Intel, 0x6, 0x55) => Penalty::Heavy,
+            // Cannon Lake, Ice Lake, Tiger Lake, Rocket Lake.
+            (Vendor::Intel, 0x6, 0x66 | 0x6A | 0x6C | 0x7D | 0x7E | 0x8C | 0x8D | 0xA7) => {
+                Penalty::Light
+            }
+            // Sapphire Rapids, Emerald Rapids, Granite Rapids.
+            (Vendor::Intel, 0x6, 0x8F | 0xCF | 0xAD | 0xAE) => Penalty::None,
+            (Vendor::AMD, 0x19, 0x10..=0x1F | 0x60..=0x7F | 0xA0..=0xAF) => Penalty::None,
+            (Vendor::AMD, 0x1A, _) => Penalty::None,
+            _ => Penalty::Unknown,
+        }
+    }
+
+    /// Approximate category of the device.
+    ///
+    /// Combines heuristics of the brand string (e.g. `Xeon`, `EPYC` for servers,
+    /// mobile model suffixes like `U` or `H`), the thermal design power and the number
+    /// of physical cores. `Unknown` if there are no or conflicting signals.
+    pub fn device_class(&self) -> DeviceClass {
+        let brand = self.device.to_lowercase();
+        let has = |keywords: &[&str]| keywords.iter().any(|keyword| brand.contains(keyword));
+
+        // Model number suffix, e.g. `8550U`, `7840HS` or `1165G7`.
+        let mobile_suffix = brand
+            .split(|c: char| c.is_whitespace() || c == '-')
+            .filter(|token| token.starts_with(|c: char| c.is_ascii_digit()))
+            .any(|token| {
+                let suffix = token.trim_start_matches(|c: char| c.is_ascii_digit());
+                matches!(suffix, "u" | "y" | "h" | "hs" | "hx" | "p")
+                    || (suffix.len() == 2
+                        && suffix.starts_with('g')
+                        && suffix[1..].chars().all(|c| c.is_ascii_digit()))
+            });
+
+        let brand_class = if has(&["xeon", "epyc", "opteron"]) {
+            Some(DeviceClass::Server)
+        } else if has(&["atom", "cortex"]) {
+            Some(DeviceClass::Embedded)
+        } else if mobile_suffix {
+            Some(DeviceClass::Laptop)
+        } else if has(&["core(tm) i", "core i", "ryzen", "pentium", "threadripper"]) {
+            Some(DeviceClass::Desktop)
+        } else {
+            None
+        };
+        let tdp_class = match self.tdp_watts {
+            Some(watts) if watts <= 28 => Some(DeviceClass::Laptop),
+            Some(watts) if watts >= 150 => Some(DeviceClass::Server),
+            _ => None,
+        };
+        let core_class = if self.physical_cores >= 32 {
+            Some(DeviceClass::Server)
+        } else {
+            None
+        };
+
+        let signals = [brand_class, tdp_class, core_class];
+        let mut signals = signals.iter().flatten();
+        match signals.next() {
+            Some(&class) if signals.all(|&other| other == class) => class,
+            _ => DeviceClass::Unknown,
+        }
+    }
+
+    /// Recommended number of worker threads for the given workload.
+    ///
+    /// Physical cores for compute-bound and logical cores for I/O-bound work, at least `1`.
+    /// Affinity and cgroup limits only apply to the properties of `system_constrained`
+    /// (as used by `PhysicalDevice::enumerate`).
+    pub fn recommended_thread_count(&self, workload: Workload) -> usize {
+        let logical_cores = self.logical_cores.max(1);
+        let threads = match workload {
+            Workload::CpuBound if self.physical_cores > 0 => self.physical_cores,
+            Workload::CpuBound | Workload::IoBound => logical_cores,
+        };
+        threads.min(logical_cores)
+    }
+
+    // Unit converted accessors, sizes are rounded down and `0` if unknown.
+
+    /// Number of physical cores.
+    pub fn physical_core_count(&self) -> usize {
+        self.physical_cores
+    }
+
+    /// Number of logical cores (hardware threads).
+    pub fn logical_core_count(&self) -> usize {
+        self.logical_cores
+    }
+
+    /// Size of the L1 data cache in KiB.
+    pub fn l1_data_cache_kib(&self) -> u32 {
+        self.l1_cache_data.size >> 10
+    }
+
+    /// Size of the L1 instruction cache in KiB.
+    pub fn l1_instruction_cache_kib(&self) -> u32 {
+        self.l1_cache_instruction.size >> 10
+    }
+
+    /// Size of the L1 cache usable for data in bytes.
+    ///
+    /// The unified L1 cache if present, the L1 data cache otherwise.
+    pub fn l1_effective_data_size(&self) -> u32 {
+        self.l1_cache_unified
+            .as_ref()
+            .map_or(self.l1_cache_data.size, |cache| cache.size)
+    }
+
+    /// Size of the L2 cache in KiB.
+    pub fn l2_cache_kib(&self) -> u32 {
+        self.l2_cache.size >> 10
+    }
+
+    /// Size of the L3 cache in MiB.
+    pub fn l3_cache_mib(&self) -> u32 {
+        self.l3_cache.size >> 20
+    }
+
+    /// Total physical memory in GiB.
+    pub fn total_memory_gib(&self) -> u64 {
+        self.total_memory_bytes >> 30
+    }
+
+    /// Currently available physical memory in MiB.
+    pub fn available_memory_mib(&self) -> u64 {
+        self.available_memory_bytes >> 20
+    }
+
+    /// Dominant cache line size in bytes, e.g. for aligning allocations.
+    ///
+    /// See `dominant_cache_line_size`.
+    pub fn cache_line_size(&self) -> usize {
+        self.dominant_cache_line_size() as usize
+    }
+
+    /// Padding in bytes to avoid false sharing, e.g. between atomics of concurrent
+    /// data structures.
+    ///
+    /// The largest line size of all cache levels. The spatial prefetchers of Intel
+    /// (since Sandy Bridge) and AMD CPUs pull pairs of adjacent lines, so the line size
+    /// is doubled for these vendors. Defaults to 128 bytes if no line size is known,
+    /// covering the adjacent line prefetch of x86 and the 128 byte lines of Apple Silicon.
+    pub fn false_sharing_guard_bytes(&self) -> usize {
+        let line_size = self
+            .caches()
+            .map(|cache| cache.properties.line_size as usize)
+            .max()
+            .unwrap_or(0);
+
+        match self.vendor {
+            _ if line_size == 0 => 128,
+            Vendor::Intel | Vendor::AMD | Vendor::Hygon => line_size * 2,
+            _ => line_size,
+        }
+    }
+
+    /// Iterate over the caches of the device, ordered by level.
+    ///
+    /// Caches with unknown size (`0`) are skipped.
+    pub fn caches(&self) -> impl Iterator<Item = CacheDescriptor<'_>> {
+        let absent = &ABSENT_CACHE;
+        let l1_cache_unified = self.l1_cache_unified.as_ref().unwrap_or(absent);
+        let caches = [
+            (1, CacheKind::Data, &self.l1_cache_data),
+            (1, CacheKind::Instruction, &self.l1_cache_instruction),
+            (1, CacheKind::Unified, l1_cache_unified),
+            (2, CacheKind::Unified, &self.l2_cache),
+            (3, CacheKind::Unified, &self.l3_cache),
+            (4, CacheKind::Unified, &self.l4_cache),
+        ];
+
+        IntoIterator::into_iter(caches)
+            .filter(|(_, _, properties)| properties.is_present())
+            .map(|(level, kind, properties)| CacheDescriptor {
+                level,
+                kind,
+                properties,
+            })
+    }
+
+    /// Total size in bytes of all caches of the device.
+    ///
+    /// The L1 and L2 caches are counted once per instance, the logical cores divided by
+    /// the sharing cores or one per physical core if the sharing isn't known.
+    /// The L3 and L4 caches are counted once per package as they're specified per package.
+    pub fn cache_total_bytes(&self) -> u64 {
+        let instances = |cache: &PhysicalDeviceCacheProperties| match cache.shared_by_cores {
+            0 => self.physical_cores,
+            sharers => self.logical_cores.div_ceil(sharers as usize),
+        };
+        let core_caches = [
+            Some(&self.l1_cache_data),
+            Some(&self.l1_cache_instruction),
+            self.l1_cache_unified.as_ref(),
+            Some(&self.l2_cache),
+        ];
+        let packages = self.package_count.max(1) as u64;
+
+        core_caches
+            .iter()
+            .flatten()
+            .map(|cache| cache.total_size_across_cores(instances(cache)))
+            .sum::<u64>()
+            + (self.l3_cache.size as u64 + self.l4_cache.size as u64) * packages
+    }
+
+    /// L2 and L3 cache capacity per hardware thread when fully subscribed.
+    ///
+    /// Each cache is divided by its number of sharing logical cores. If the sharing
+    /// isn't known, L2 is assumed to be shared by the threads of a physical core
+    /// and L3 by all logical cores.
+    pub fn effective_cache_per_thread(&self) -> EffectiveCachePerThread {
+        let per_thread = |cache: &PhysicalDeviceCacheProperties, fallback: usize| {
+            let sharers = match cache.shared_by_cores {
+                0 => fallback.max(1) as u32,
+                sharers => sharers,
+            };
+            cache.size / sharers
+        };
+
+        EffectiveCachePerThread {
+            l2_per_thread: per_thread(&self.l2_cache, self.threads_per_core),
+            l3_per_thread: per_thread(&self.l3_cache, self.logical_cores),
+        }
+    }
+
+    /// Twice the L2 line size, or the dominant line size if the L2 line size is unknown.
+    #[cfg(any(feature = "std", target_arch = "x86", target_arch = "x86_64"))]
+    fn default_prefetch_bytes(&self) -> u32 {
+        let line_size = match self.l2_cache.line_size {
+            0 => self.dominant_cache_line_size(),
+            line_size => line_size,
+        };
+        2 * line_size
+    }
+
+    /// Most common non-zero cache line size across all cache levels.
+    ///
+    /// Ties are resolved in favor of the larger line size.
+    /// Returns `0` if no cache reports a line size.
+    pub fn dominant_cache_line_size(&self) -> u32 {
+        let line_sizes = [
+            self.l1_cache_data.line_size,
+            self.l1_cache_instruction.line_size,
+            self.l1_cache_unified
+                .as_ref()
+                .map_or(0, |cache| cache.line_size),
+            self.l2_cache.line_size,
+            self.l3_cache.line_size,
+        ];
+
+        line_sizes
+            .iter()
+            .copied()
+            .filter(|&line_size| line_size > 0)
+            .max_by_key(|&line_size| {
+                let count = line_sizes
+                    .iter()
+                    .filter(|&&other| other == line_size)
+                    .count();
+                (count, line_size)
+            })
+            .unwrap_or(0)
+    }
+}
+
+impl fmt::Display for PhysicalDeviceProperties {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.device.is_empty() {
+            writeln!(f, "{}", self.vendor)?;
+        } else {
+            writeln!(f, "{}", self.device)?;
+        }
+        writeln!(
+            f,
+            "{} cores ({} threads)",
+            self.physical_cores, self.logical_cores
+        )?;
+
+        let caches = [
+            ("L1d", &self.l1_cache_data),
+            ("L1i", &self.l1_cache_instruction),
+            (
+                "L1",
+                self.l1_cache_unified.as_ref().unwrap_or(&ABSENT_CACHE),
+            ),
+            ("L2", &self.l2_cache),
+            ("L3", &self.l3_cache),
+            ("L4", &self.l4_cache),
+        ];
+        let mut first = true;
+        for (name, cache) in caches.iter().filter(|(_, cache)| cache.is_present()) {
+            if !first {
+                write!(f, ", ")?;
+            }
+            write!(f, "{} {}", name, ByteSize(cache.size as _))?;
+            first = false;
+        }
+
+        Ok(())
+    }
+}
+
+/// Orders by vendor, device name and number of physical cores.
 ///
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Remaining fields only break ties to stay consistent with `Eq`.
+impl Ord for PhysicalDeviceProperties {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.vendor
+            .cmp(&other.vendor)
+            .then_with(|| self.device.cmp(&other.device))
+            .then_with(|| self.physical_cores.cmp(&other.physical_cores))
+            .then_with(|| {
+                (self.family, self.model, self.stepping).cmp(&(
+                    other.family,
+                    other.model,
+                    other.stepping,
+                ))
+            })
+            .then_with(|| self.microarchitecture.cmp(&other.microarchitecture))
+            .then_with(|| self.logical_cores.cmp(&other.logical_cores))
+            .then_with(|| self.threads_per_core.cmp(&other.threads_per_core))
+            .then_with(|| self.package_count.cmp(&other.package_count))
+            .then_with(|| self.l1_cache_data.cmp(&other.l1_cache_data))
+            .then_with(|| self.l1_cache_instruction.cmp(&other.l1_cache_instruction))
+            .then_with(|| self.l1_cache_unified.cmp(&other.l1_cache_unified))
+            .then_with(|| self.l2_cache.cmp(&other.l2_cache))
+            .then_with(|| self.l3_cache.cmp(&other.l3_cache))
+            .then_with(|| self.l4_cache.cmp(&other.l4_cache))
+            .then_with(|| {
+                self.recommended_prefetch_bytes
+                    .cmp(&other.recommended_prefetch_bytes)
+            })
+            .then_with(|| self.l1_tlb_data.cmp(&other.l1_tlb_data))
+            .then_with(|| self.l1_tlb_instruction.cmp(&other.l1_tlb_instruction))
+            .then_with(|| self.l2_tlb.cmp(&other.l2_tlb))
+            .then_with(|| self.base_frequency_mhz.cmp(&other.base_frequency_mhz))
+            .then_with(|| self.max_frequency_mhz.cmp(&other.max_frequency_mhz))
+            .then_with(|| self.current_frequency_mhz.cmp(&other.current_frequency_mhz))
+            .then_with(|| self.turbo_enabled.cmp(&other.turbo_enabled))
+            .then_with(|| self.invariant_tsc.cmp(&other.invariant_tsc))
+            .then_with(|| self.tdp_watts.cmp(&other.tdp_watts))
+            .then_with(|| self.total_memory_bytes.cmp(&other.total_memory_bytes))
+            .then_with(|| {
+                self.available_memory_bytes
+                    .cmp(&other.available_memory_bytes)
+            })
+            .then_with(|| self.large_page_sizes.cmp(&other.large_page_sizes))
+            .then_with(|| self.core_clusters.cmp(&other.core_clusters))
+            .then_with(|| self.core_complexes.cmp(&other.core_complexes))
+            .then_with(|| self.numa_nodes.cmp(&other.numa_nodes))
+            .then_with(|| self.hypervisor.cmp(&other.hypervisor))
+            .then_with(|| self.translation.cmp(&other.translation))
+            .then_with(|| self.security_features.cmp(&other.security_features))
+            .then_with(|| self.pmu_counters.cmp(&other.pmu_counters))
+            .then_with(|| self.endianness.cmp(&other.endianness))
+            .then_with(|| self.pointer_width_bits.cmp(&other.pointer_width_bits))
+    }
+}
+
+impl PartialOrd for PhysicalDeviceProperties {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Formats a size in bytes with the largest binary unit dividing it evenly.
+pub(crate) struct ByteSize(pub u64);
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const UNITS: [(u64, &str); 3] = [(1 << 30, "GiB"), (1 << 20, "MiB"), (1 << 10, "KiB")];
+
+        for &(scale, unit) in &UNITS {
+            if self.0 >= scale && self.0.is_multiple_of(scale) {
+                return write!(f, "{} {}", self.0 / scale, unit);
+            }
+        }
+        write!(f, "{} B", self.0)
+    }
+}
+
+/// Decode the vendor from the CPUID leaf `0` vendor string registers.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn cpuid_vendor(ebx: u32, edx: u32, ecx: u32) -> Vendor {
+    let mut brand = [0u8; 12];
+    brand[0..4].copy_from_slice(&ebx.to_le_bytes());
+    brand[4..8].copy_from_slice(&edx.to_le_bytes());
+    brand[8..12].copy_from_slice(&ecx.to_le_bytes());
+
+    core::str::from_utf8(&brand).map_or(Vendor::Unknown, Vendor::from_cpuid_string)
+}
+
+/// CPUID leaf of the x2APIC topology, `0x1F` if populated or `0xB`.
+#[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))]
+fn x2apic_leaf() -> Option<u32> {
+    use crate::cpuid::cpuid;
+
+    if cpuid(0x1F, 0).is_some_and(|leaf| leaf.ebx != 0) {
+        Some(0x1F)
+    } else if cpuid(0xB, 0).is_some_and(|leaf| leaf.ebx != 0) {
+        Some(0xB)
+    } else {
+        None
+    }
+}
+
+/// Group logical cores with their `(x2APIC ID, SMT shift)` into physical cores.
+#[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))]
+fn group_smt_siblings(ids: &[(usize, (u32, u32))]) -> Vec<CoreAffinity> {
+    let mut cores = Vec::<(u32, CoreAffinity)>::new();
+    for &(cpu, (x2apic_id, smt_shift)) in ids {
+        let core_id = x2apic_id.checked_shr(smt_shift).unwrap_or(0);
+        match cores.iter_mut().find(|(id, _)| *id == core_id) {
+            Some((_, core)) => core.logical_cores.push(cpu),
+            None => cores.push((
+                core_id,
+                CoreAffinity {
+                    logical_cores: vec![cpu],
+                },
+            )),
+        }
+    }
+
+    cores.into_iter().map(|(_, core)| core).collect()
+}
+
+/// L3 cache of a logical core as reported by CPUID leaf `0x8000001D`.
+#[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))]
+struct L3Slice {
+    apic_id: u32,
+    shared_by_cores: u32,
+    size: u32,
+}
+
+/// Group logical cores into core complexes by the APIC ID bits above the L3 sharing.
+#[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))]
+fn group_core_complexes(slices: &[(usize, L3Slice)]) -> Vec<CoreComplex> {
+    let mut complexes = Vec::<(u32, CoreComplex)>::new();
+    for (cpu, slice) in slices {
+        let shift = slice
+            .shared_by_cores
+            .max(1)
+            .next_power_of_two()
+            .trailing_zeros();
+        let complex_id = slice.apic_id >> shift;
+        match complexes.iter_mut().find(|(id, _)| *id == complex_id) {
+            Some((_, complex)) => complex.logical_cores.push(*cpu),
+            None => complexes.push((
+                complex_id,
+                CoreComplex {
+                    logical_cores: vec![*cpu],
+                    l3_cache_bytes: slice.size,
+                },
+            )),
+        }
+    }
+
+    complexes.into_iter().map(|(_, complex)| complex).collect()
+}
+
+/// Store a cache in the matching slot of `[l1_data, l1_instruction, l2, l3, l4, l1_unified]`.
+///
+/// Higher levels have a single slot regardless of type.
+/// The largest cache is kept per slot.
+#[cfg(any(
+    target_arch = "x86",
+    target_arch = "x86_64",
+    all(feature = "std", target_os = "linux", target_arch = "aarch64")
+))]
+pub(crate) fn insert_cache(
+    caches: &mut [PhysicalDeviceCacheProperties; 6],
+    kind: CacheKind,
+    level: u32,
+    properties: PhysicalDeviceCacheProperties,
+) {
+    let slot = match (level, kind) {
+        (1, CacheKind::Data) => 0,
+        (1, CacheKind::Instruction) => 1,
+        (1, CacheKind::Unified) => 5,
+        (2, _) => 2,
+        (3, _) => 3,
+        (4, _) => 4,
+        _ => return,
+    };
+
+    if properties.size > caches[slot].size {
+        caches[slot] = properties;
+    }
+}
+
+/// Decode family, model and stepping from the CPUID leaf `1` EAX register.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn cpuid_family_model_stepping(eax: u32) -> (u32, u32, u32) {
+    let stepping = eax & 0xF;
+    let base_model = (eax >> 4) & 0xF;
+    let base_family = (eax >> 8) & 0xF;
+    let extended_model = (eax >> 16) & 0xF;
+    let extended_family = (eax >> 20) & 0xFF;
+
+    let family = if base_family == 0xF {
+        base_family + extended_family
+    } else {
+        base_family
+    };
+    let model = if base_family == 0x6 || base_family == 0xF {
+        (extended_model << 4) | base_model
+    } else {
+        base_model
+    };
+
+    (family, model, stepping)
+}
+
+/// Known L2 and L3 inclusivity of AMD families.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn amd_cache_inclusivity(family: u32) -> (CacheInclusivity, CacheInclusivity) {
+    match family {
+        // K10: exclusive L2, L3 is a victim cache.
+        0x10..=0x12 => (CacheInclusivity::Exclusive, CacheInclusivity::Exclusive),
+        // Bulldozer and Zen: L2 includes L1, L3 is a victim cache of L2.
+        0x15 | 0x17..=0x1A => (CacheInclusivity::Inclusive, CacheInclusivity::Exclusive),
+        _ => (CacheInclusivity::Unknown, CacheInclusivity::Unknown),
+    }
+}
+
+/// Map family and model to a microarchitecture name.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn microarchitecture(vendor: Vendor, family: u32, model: u32) -> Option<&'static str> {
+    let name = match (vendor, family, model) {
+        (Vendor::AMD, 0x15, 0x01) => "Bulldozer",
+        (Vendor::AMD, 0x15, 0x02 | 0x10..=0x1F) => "Piledriver",
+        (Vendor::AMD, 0x15, 0x30..=0x3F) => "Steamroller",
+        (Vendor::AMD, 0x15, 0x60..=0x7F) => "Excavator",
+        (Vendor::AMD, 0x16, 0x00..=0x0F) => "Jaguar",
+        (Vendor::AMD, 0x16, 0x30..=0x3F) => "Puma",
+        (Vendor::AMD, 0x17, 0x08 | 0x18) => "Zen+",
+        (Vendor::AMD, 0x17, 0x00..=0x2F) => "Zen",
+        (Vendor::AMD, 0x17, _) => "Zen 2",
+        (Vendor::AMD, 0x19, 0x10..=0x1F | 0x60..=0x7F | 0xA0..=0xAF) => "Zen 4",
+        (Vendor::AMD, 0x19, 0x40..=0x4F) => "Zen 3+",
+        (Vendor::AMD, 0x19, _) => "Zen 3",
+        (Vendor::AMD, 0x1A, _) => "Zen 5",
+        (Vendor::Hygon, 0x18, _) => "Zen",
+        (Vendor::Intel, 0x6, model) => match model {
+            0x0F => "Merom",
+            0x17 | 0x1D => "Penryn",
+            0x1A | 0x1E | 0x1F | 0x2E => "Nehalem",
+            0x25 | 0x2C | 0x2F => "Westmere",
+            0x2A | 0x2D => "Sandy Bridge",
+            0x3A | 0x3E => "Ivy Bridge",
+            0x3C | 0x3F | 0x45 | 0x46 => "Haswell",
+            0x3D | 0x47 | 0x4F | 0x56 => "Broadwell",
+            0x4E | 0x5E | 0x55 => "Skylake",
+            0x8E | 0x9E => "Kaby Lake",
+            0xA5 | 0xA6 => "Comet Lake",
+            0x66 => "Cannon Lake",
+            0x6A | 0x6C | 0x7D | 0x7E => "Ice Lake",
+            0x8C | 0x8D => "Tiger Lake",
+            0xA7 => "Rocket Lake",
+            0x97 | 0x9A => "Alder Lake",
+            0xB7 | 0xBA | 0xBF => "Raptor Lake",
+            0x8F => "Sapphire Rapids",
+            0xCF => "Emerald Rapids",
+            0xAA | 0xAC => "Meteor Lake",
+            0xC5 | 0xC6 => "Arrow Lake",
+            0xBD => "Lunar Lake",
+            0x5C => "Goldmont",
+            0x7A => "Goldmont Plus",
+            0x86 | 0x96 | 0x9C => "Tremont",
+            0xBE => "Gracemont",
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    Some(name)
+}
+
+/// Decode the brand string from the CPUID leaves `0x80000002..=0x80000004` registers,
+/// given as `eax, ebx, ecx, edx` of each leaf.
+///
+/// The string is stored little-endian in each register and terminated by a null byte.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn cpuid_brand(registers: &[u32; 12]) -> String {
+    let name = registers
+        .iter()
+        .flat_map(|register| register.to_le_bytes())
+        .take_while(|&byte| byte != 0)
+        .map(char::from)
+        .collect::<String>();
+
+    String::from(name.trim())
+}
+
+/// Detect QEMU binary translation (TCG) from the brand string and hypervisor signature.
+///
+/// QEMU user mode emulation reports its own brand string without a hypervisor,
+/// system emulation the `TCGTCGTCGTCG` signature. QEMU CPU models accelerated by
+/// KVM use the same brand string but run natively.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn cpuid_translation(brand: &str, hypervisor: Option<&Hypervisor>) -> Option<Translation> {
+    match hypervisor {
+        Some(Hypervisor::QEMU) => Some(Translation::Qemu),
+        None if brand.trim_start().starts_with("QEMU") => Some(Translation::Qemu),
+        _ => None,
+    }
+}
+
+/// Decode the hypervisor from the CPUID leaf `0x40000000` signature registers.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn cpuid_hypervisor(ebx: u32, ecx: u32, edx: u32) -> Hypervisor {
+    let mut signature = [0u8; 12];
+    signature[0..4].copy_from_slice(&ebx.to_le_bytes());
+    signature[4..8].copy_from_slice(&ecx.to_le_bytes());
+    signature[8..12].copy_from_slice(&edx.to_le_bytes());
+
+    match &signature {
+        b"KVMKVMKVM\0\0\0" => Hypervisor::KVM,
+        b"Microsoft Hv" => Hypervisor::HyperV,
+        b"VMwareVMware" => Hypervisor::VMware,
+        b"XenVMMXenVMM" => Hypervisor::Xen,
+        b"TCGTCGTCGTCG" => Hypervisor::QEMU,
+        _ => {
+            let signature = String::from_utf8_lossy(&signature);
+            Hypervisor::Unknown(String::from(signature.trim_end_matches('\0').trim()))
+        }
+    }
+}
+
+/// Parse the frequency in MHz from brand strings like `... CPU @ 3.40GHz`.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn parse_brand_frequency(brand: &str) -> Option<u32> {
+    let (_, frequency) = brand.rsplit_once('@')?;
+    let frequency = frequency.trim();
+
+    let (value, scale) = if let Some(value) = frequency.strip_suffix("GHz") {
+        (value, 1000.0)
+    } else if let Some(value) = frequency.strip_suffix("MHz") {
+        (value, 1.0)
+    } else {
+        return None;
+    };
+
+    let value = value.trim().parse::<f32>().ok()?;
+    // Round to nearest, `f32::round` requires `std`.
+    Some((value * scale + 0.5) as u32)
+}
+
+/// Device Hardware Vendor
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Vendor {
     Intel,
     AMD,
+    /// Hygon (AMD Zen derived).
+    Hygon,
+    /// Zhaoxin (Shanghai).
+    Zhaoxin,
+    /// VIA (Centaur).
+    Via,
+    /// Apple Silicon.
+    Apple,
+    /// ARM based designs.
+    Arm,
+    #[cfg_attr(feature = "serde", serde(other))]
     Unknown,
 }
+
+impl Vendor {
+    /// Classify a CPUID leaf `0` vendor string, e.g. `"GenuineIntel"`.
+    pub fn from_cpuid_string(vendor: &str) -> Vendor {
+        match vendor {
+            "AuthenticAMD" | "AMDisbetter!" => Vendor::AMD,
+            "GenuineIntel" => Vendor::Intel,
+            "HygonGenuine" => Vendor::Hygon,
+            "  Shanghai  " => Vendor::Zhaoxin,
+            "CentaurHauls" | "VIA VIA VIA " => Vendor::Via,
+            _ => Vendor::Unknown,
+        }
+    }
+
+    /// CPUID leaf `0` vendor string of the vendor.
+    ///
+    /// `None` for vendors without x86 processors.
+    pub fn cpuid_signature(&self) -> Option<&'static str> {
+        match self {
+            Vendor::Intel => Some("GenuineIntel"),
+            Vendor::AMD => Some("AuthenticAMD"),
+            Vendor::Hygon => Some("HygonGenuine"),
+            Vendor::Zhaoxin => Some("  Shanghai  "),
+            Vendor::Via => Some("CentaurHauls"),
+            Vendor::Apple | Vendor::Arm | Vendor::Unknown => None,
+        }
+    }
+}
+
+impl fmt::Display for Vendor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Vendor::Intel => "Intel",
+            Vendor::AMD => "AMD",
+            Vendor::Hygon => "Hygon",
+            Vendor::Zhaoxin => "Zhaoxin",
+            Vendor::Via => "VIA",
+            Vendor::Apple => "Apple",
+            Vendor::Arm => "ARM",
+            Vendor::Unknown => "Unknown",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Byte Order
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+impl Endianness {
+    /// Byte order of the compilation target.
+    pub const fn native() -> Self {
+        if cfg!(target_endian = "big") {
+            Endianness::Big
+        } else {
+            Endianness::Little
+        }
+    }
+}
+
+impl fmt::Display for Endianness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Endianness::Little => "little",
+            Endianness::Big => "big",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Hypervisor Vendor
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Hypervisor {
+    KVM,
+    /// Microsoft Hyper-V.
+    HyperV,
+    VMware,
+    Xen,
+    /// QEMU without hardware acceleration (TCG).
+    QEMU,
+    /// Unrecognized hypervisor with its signature.
+    Unknown(String),
+}
+
+impl fmt::Display for Hypervisor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Hypervisor::KVM => "KVM",
+            Hypervisor::HyperV => "Hyper-V",
+            Hypervisor::VMware => "VMware",
+            Hypervisor::Xen => "Xen",
+            Hypervisor::QEMU => "QEMU",
+            Hypervisor::Unknown(signature) => signature,
+        };
+        f.write_str(name)
+    }
+}
+
+/// Binary Translator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Translation {
+    /// Apple Rosetta 2, x86_64 binaries on Apple Silicon.
+    Rosetta2,
+    /// QEMU user mode or system emulation (TCG).
+    Qemu,
+    /// Unrecognized translator, e.g. the x86_64 emulation of Windows on ARM.
+    Unknown,
+}
+
+impl fmt::Display for Translation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Translation::Rosetta2 => "Rosetta 2",
+            Translation::Qemu => "QEMU",
+            Translation::Unknown => "unknown",
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(all(test, any(target_arch = "x86", target_arch = "x86_64")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitized_cache() {
+        let cache = PhysicalDeviceCacheProperties::from_geometry(512 << 20, 64, 8);
+        assert!(!cache.clone().sanitized(1).is_present());
+        assert!(cache.sanitized(3).is_present());
+
+        let cache = PhysicalDeviceCacheProperties::from_geometry(32 * 1024, 48, 8);
+        assert!(!cache.sanitized(1).is_present());
+
+        // Saturated 32 GiB cache.
+        let cache = PhysicalDeviceCacheProperties {
+            size: u32::MAX,
+            sets: 1 << 23,
+            ..PhysicalDeviceCacheProperties::from_geometry(0, 64, 64)
+        };
+        assert!(!cache.sanitized(3).is_present());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn smt_siblings() {
+        let ids = [
+            (0, (0, 1)),
+            (1, (2, 1)),
+            (2, (1, 1)),
+            (3, (3, 1)),
+            (4, (4, 0)),
+        ];
+
+        let cores = group_smt_siblings(&ids);
+        assert_eq!(cores.len(), 3);
+        assert_eq!(cores[0].logical_cores, [0, 2]);
+        assert_eq!(cores[1].logical_cores, [1, 3]);
+        assert_eq!(cores[2].logical_cores, [4]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn core_complexes() {
+        // Two partially enabled CCX, each L3 slice covers a range of 8 APIC IDs.
+        let slices = [0, 1, 2, 3, 8, 9, 10, 11]
+            .iter()
+            .enumerate()
+            .map(|(cpu, &apic_id)| {
+                let slice = L3Slice {
+                    apic_id,
+                    shared_by_cores: 8,
+                    size: 32 << 20,
+                };
+                (cpu, slice)
+            })
+            .collect::<Vec<_>>();
+
+        let complexes = group_core_complexes(&slices);
+        assert_eq!(complexes.len(), 2);
+        assert_eq!(complexes[0].logical_cores, [0, 1, 2, 3]);
+        assert_eq!(complexes[1].logical_cores, [4, 5, 6, 7]);
+        assert_eq!(complexes[1].l3_cache_bytes, 32 << 20);
+    }
+
+    #[test]
+    fn vendor_registers() {
+        assert_eq!(
+            cpuid_vendor(0x756e_6547, 0x4965_6e69, 0x6c65_746e),
+            Vendor::Intel
+        );
+        assert_eq!(
+            cpuid_vendor(0x6874_7541, 0x6974_6e65, 0x444d_4163),
+            Vendor::AMD
+        );
+        assert_eq!(cpuid_vendor(0, 0, 0), Vendor::Unknown);
+    }
+
+    #[test]
+    fn hypervisor_registers() {
+        assert_eq!(
+            cpuid_hypervisor(0x4b4d_564b, 0x564b_4d56, 0x0000_004d),
+            Hypervisor::KVM
+        );
+        assert_eq!(
+            cpuid_hypervisor(0x7263_694d, 0x666f_736f, 0x7648_2074),
+            Hypervisor::HyperV
+        );
+        assert_eq!(
+            cpuid_hypervisor(0x4e52_4341, 0x4e52_4341, 0x4e52_4341),
+            Hypervisor::Unknown(String::from("ACRNACRNACRN"))
+        );
+    }
+
+    #[test]
+    fn translation() {
+        let qemu = "QEMU Virtual CPU version 2.5+";
+        assert_eq!(cpuid_translation(qemu, None), Some(Translation::Qemu));
+        assert_eq!(
+            cpuid_translation(qemu, Some(&Hypervisor::QEMU)),
+            Some(Translation::Qemu)
+        );
+        // QEMU CPU model accelerated by KVM.
+        assert_eq!(cpuid_translation(qemu, Some(&Hypervisor::KVM)), None);
+        assert_eq!(
+            cpuid_translation("AMD Ryzen 9 5950X 16-Core Processor", None),
+            None
+        );
+    }
+
+    #[test]
+    fn cpuid_cache_slots() {
+        let cache = |size| PhysicalDeviceCacheProperties {
+            size,
+            ..Default::default()
+        };
+        let mut caches: [PhysicalDeviceCacheProperties; 6] = Default::default();
+
+        insert_cache(&mut caches, CacheKind::Data, 1, cache(48 * 1024));
+        insert_cache(&mut caches, CacheKind::Instruction, 1, cache(32 * 1024));
+        // Typed and unified L2.
+        insert_cache(&mut caches, CacheKind::Data, 2, cache(1024 * 1024));
+        insert_cache(&mut caches, CacheKind::Unified, 2, cache(2048 * 1024));
+        insert_cache(&mut caches, CacheKind::Unified, 3, cache(32 * 1024 * 1024));
+        insert_cache(&mut caches, CacheKind::Unified, 4, cache(128 * 1024 * 1024));
+        insert_cache(&mut caches, CacheKind::Unified, 5, cache(256 * 1024 * 1024));
+
+        let sizes = caches.iter().map(|cache| cache.size).collect::<Vec<_>>();
+        assert_eq!(
+            sizes,
+            [
+                48 * 1024,
+                32 * 1024,
+                2048 * 1024,
+                32 * 1024 * 1024,
+                128 * 1024 * 1024,
+                0
+            ]
+        );
+
+        // Unified L1 has a separate slot.
+        let mut caches: [PhysicalDeviceCacheProperties; 6] = Default::default();
+        insert_cache(&mut caches, CacheKind::Unified, 1, cache(64 * 1024));
+        assert!(!caches[0].is_present() && !caches[1].is_present());
+        assert_eq!(caches[5].size, 64 * 1024);
+    }
+
+    #[test]
+    fn brand_registers() {
+        let registers = [
+            0x2044_4d41,
+            0x657a_7952,
+            0x2039_206e,
+            0x3035_3935,
+            0x3631_2058,
+            0x726f_432d,
+            0x7250_2065,
+            0x7365_636f,
+            0x0072_6f73,
+            0x0000_0000,
+            0x0000_0000,
+            0x0000_0000,
+        ];
+        assert_eq!(
+            cpuid_brand(&registers),
+            "AMD Ryzen 9 5950X 16-Core Processor"
+        );
+        assert_eq!(cpuid_brand(&[0; 12]), "");
+    }
+
+    #[test]
+    fn family_model_stepping() {
+        // AMD Ryzen 9 5950X
+        assert_eq!(cpuid_family_model_stepping(0x00A2_0F10), (0x19, 0x21, 0));
+        // Intel Core i7-6700K
+        assert_eq!(cpuid_family_model_stepping(0x0005_06E3), (0x6, 0x5E, 3));
+
+        assert_eq!(microarchitecture(Vendor::AMD, 0x19, 0x21), Some("Zen 3"));
+        assert_eq!(microarchitecture(Vendor::Intel, 0x6, 0x5E), Some("Skylake"));
+        assert_eq!(microarchitecture(Vendor::Intel, 0xF, 0x01), None);
+    }
+
+    #[test]
+    fn random_registers() {
+        let results = crate::cpuid::random_registers(12_000).collect::<Vec<_>>();
+
+        for result in &results {
+            let (family, model, stepping) = cpuid_family_model_stepping(result.eax);
+            assert!(family <= 0xF + 0xFF && model <= 0xFF && stepping <= 0xF);
+            microarchitecture(Vendor::AMD, family, model);
+            microarchitecture(Vendor::Intel, family, model);
+
+            cpuid_vendor(result.ebx, result.edx, result.ecx);
+            cpuid_hypervisor(result.ebx, result.ecx, result.edx);
+        }
+
+        for chunk in results.chunks_exact(3) {
+            let mut registers = [0; 12];
+            for (i, result) in chunk.iter().enumerate() {
+                registers[i * 4..][..4]
+                    .copy_from_slice(&[result.eax, result.ebx, result.ecx, result.edx]);
+            }
+            let brand = cpuid_brand(&registers);
+            assert!(brand.chars().count() <= 48);
+            parse_brand_frequency(&brand);
+        }
+    }
+}
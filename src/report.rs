@@ -0,0 +1,300 @@
+use crate::properties::{
+    CacheInclusivity, CacheWritePolicy, PhysicalDeviceCacheProperties, PhysicalDeviceProperties,
+    PhysicalDeviceTlbProperties,
+};
+use alloc::string::String;
+use core::fmt::{self, Write};
+
+/// Format logical core indices as compact list like `0-3,8-11`.
+fn write_cpu_list(out: &mut String, cpus: &[usize]) -> fmt::Result {
+    let mut start = 0;
+    while start < cpus.len() {
+        let mut end = start;
+        while end + 1 < cpus.len() && cpus[end + 1] == cpus[end] + 1 {
+            end += 1;
+        }
+        if start > 0 {
+            out.push(',');
+        }
+        if start == end {
+            write!(out, "{}", cpus[start])?;
+        } else {
+            write!(out, "{}-{}", cpus[start], cpus[end])?;
+        }
+        start = end + 1;
+    }
+    Ok(())
+}
+
+fn write_cache(out: &mut String, name: &str, cache: &PhysicalDeviceCacheProperties) -> fmt::Result {
+    let inclusivity = match cache.inclusivity {
+        CacheInclusivity::Inclusive => "inclusive",
+        CacheInclusivity::Exclusive => "exclusive",
+        CacheInclusivity::Unknown => "unknown",
+    };
+    let write_policy = match cache.write_policy {
+        CacheWritePolicy::WriteBack => "write-back",
+        CacheWritePolicy::WriteThrough => "write-through",
+        CacheWritePolicy::Unknown => "unknown",
+    };
+
+    writeln!(out)?;
+    writeln!(out, "[cache.{}]", name)?;
+    writeln!(out, "size_bytes: {}", cache.size)?;
+    writeln!(out, "line_size: {}", cache.line_size)?;
+    writeln!(out, "associativity: {}", cache.associativity)?;
+    writeln!(out, "sets: {}", cache.sets)?;
+    writeln!(out, "inclusivity: {}", inclusivity)?;
+    writeln!(out, "write_policy: {}", write_policy)?;
+    writeln!(out, "shared_by_cores: {}", cache.shared_by_cores)
+}
+
+fn write_tlb(out: &mut String, name: &str, tlb: &PhysicalDeviceTlbProperties) -> fmt::Result {
+    writeln!(out)?;
+    writeln!(out, "[tlb.{}]", name)?;
+    writeln!(out, "entries: {}", tlb.entries)?;
+    writeln!(out, "page_size_bytes: {}", tlb.page_size_bytes)
+}
+
+impl PhysicalDeviceProperties {
+    /// Verbose plain-text report of all properties, e.g. for bug reports or snapshot tests.
+    ///
+    /// Sections and fields are written in a stable order, one value per line.
+    pub fn to_report_string(&self) -> String {
+        let mut out = String::new();
+        self.write_report(&mut out)
+            .expect("writing to a string can't fail");
+        out
+    }
+
+    fn write_report(&self, out: &mut String) -> fmt::Result {
+        writeln!(out, "[device]")?;
+        writeln!(out, "vendor: {}", self.vendor)?;
+        writeln!(out, "device: {}", self.device)?;
+        writeln!(out, "family: {:#x}", self.family)?;
+        writeln!(out, "model: {:#x}", self.model)?;
+        writeln!(out, "stepping: {}", self.stepping)?;
+        writeln!(
+            out,
+            "microarchitecture: {}",
+            self.microarchitecture.as_deref().unwrap_or("unknown")
+        )?;
+        match &self.hypervisor {
+            Some(hypervisor) => writeln!(out, "hypervisor: {}", hypervisor)?,
+            None => writeln!(out, "hypervisor: none")?,
+        }
+        match &self.translation {
+            Some(translation) => writeln!(out, "translation: {}", translation)?,
+            None => writeln!(out, "translation: none")?,
+        }
+        writeln!(out, "endianness: {}", self.endianness)?;
+        writeln!(out, "pointer_width_bits: {}", self.pointer_width_bits)?;
+
+        writeln!(out)?;
+        writeln!(out, "[cores]")?;
+        writeln!(out, "logical: {}", self.logical_cores)?;
+        writeln!(out, "physical: {}", self.physical_cores)?;
+        writeln!(out, "threads_per_core: {}", self.threads_per_core)?;
+        writeln!(out, "packages: {}", self.package_count)?;
+
+        writeln!(out)?;
+        writeln!(out, "[frequency]")?;
+        writeln!(out, "base_mhz: {}", self.base_frequency_mhz)?;
+        writeln!(out, "max_mhz: {}", self.max_frequency_mhz)?;
+        writeln!(out, "current_mhz: {}", self.current_frequency_mhz)?;
+        match self.turbo_enabled {
+            Some(true) => writeln!(out, "turbo: enabled")?,
+            Some(false) => writeln!(out, "turbo: disabled")?,
+            None => writeln!(out, "turbo: unknown")?,
+        }
+        writeln!(out, "invariant_tsc: {}", self.invariant_tsc)?;
+        match self.tdp_watts {
+            Some(watts) => writeln!(out, "tdp_watts: {}", watts)?,
+            None => writeln!(out, "tdp_watts: unknown")?,
+        }
+
+        let security = &self.security_features;
+        writeln!(out)?;
+        writeln!(out, "[security]")?;
+        writeln!(out, "sgx: {}", security.sgx)?;
+        if security.sgx {
+            writeln!(
+                out,
+                "sgx_max_enclave_bytes: {} (32-bit) {} (64-bit)",
+                security.sgx_max_enclave_bytes_32bit, security.sgx_max_enclave_bytes_64bit
+            )?;
+            writeln!(out, "sgx_epc_bytes: {}", security.sgx_epc_bytes)?;
+        }
+        writeln!(out, "sev: {}", security.sev)?;
+        writeln!(out, "tme: {}", security.tme)?;
+
+        let pmu = &self.pmu_counters;
+        writeln!(out)?;
+        writeln!(out, "[pmu]")?;
+        writeln!(out, "version: {}", pmu.version)?;
+        writeln!(
+            out,
+            "general_purpose_counters: {} ({} bit)",
+            pmu.general_purpose_counters, pmu.general_purpose_counter_bits
+        )?;
+        writeln!(
+            out,
+            "fixed_counters: {} ({} bit)",
+            pmu.fixed_counters, pmu.fixed_counter_bits
+        )?;
+
+        writeln!(out)?;
+        writeln!(out, "[memory]")?;
+        writeln!(out, "total_bytes: {}", self.total_memory_bytes)?;
+        writeln!(out, "available_bytes: {}", self.available_memory_bytes)?;
+        write!(out, "large_page_sizes_bytes:")?;
+        for size in &self.large_page_sizes {
+            write!(out, " {}", size)?;
+        }
+        writeln!(out)?;
+        writeln!(
+            out,
+            "recommended_prefetch_bytes: {}",
+            self.recommended_prefetch_bytes
+        )?;
+
+        write_cache(out, "l1d", &self.l1_cache_data)?;
+        write_cache(out, "l1i", &self.l1_cache_instruction)?;
+        if let Some(l1_cache_unified) = &self.l1_cache_unified {
+            write_cache(out, "l1", l1_cache_unified)?;
+        }
+        write_cache(out, "l2", &self.l2_cache)?;
+        write_cache(out, "l3", &self.l3_cache)?;
+        if self.l4_cache.is_present() {
+            write_cache(out, "l4", &self.l4_cache)?;
+        }
+
+        write_tlb(out, "l1d", &self.l1_tlb_data)?;
+        write_tlb(out, "l1i", &self.l1_tlb_instruction)?;
+        write_tlb(out, "l2", &self.l2_tlb)?;
+
+        for cluster in &self.core_clusters {
+            writeln!(out)?;
+            writeln!(out, "[core_cluster]")?;
+            writeln!(out, "core_type: {:?}", cluster.core_type)?;
+            write!(out, "logical_cores: ")?;
+            write_cpu_list(out, &cluster.logical_cores)?;
+            writeln!(out)?;
+        }
+
+        for complex in &self.core_complexes {
+            writeln!(out)?;
+            writeln!(out, "[core_complex]")?;
+            writeln!(out, "l3_bytes: {}", complex.l3_cache_bytes)?;
+            write!(out, "logical_cores: ")?;
+            write_cpu_list(out, &complex.logical_cores)?;
+            writeln!(out)?;
+        }
+
+        for node in &self.numa_nodes {
+            writeln!(out)?;
+            writeln!(out, "[numa_node.{}]", node.node_id)?;
+            write!(out, "logical_cores: ")?;
+            write_cpu_list(out, &node.logical_cores)?;
+            writeln!(out)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl crate::device::PhysicalDevice {
+    /// Machine-readable snapshot of everything detected, to be attached to bug reports.
+    ///
+    /// Contains the property report, the features and on x86 the raw CPUID leaves
+    /// consulted during the detection, formatted as `leaf.subleaf: eax ebx ecx edx`.
+    pub fn debug_snapshot(&self) -> String {
+        let mut out = String::new();
+        self.write_snapshot(&mut out)
+            .expect("writing to a string can't fail");
+        out
+    }
+
+    fn write_snapshot(&self, out: &mut String) -> fmt::Result {
+        writeln!(out, "[snapshot]")?;
+        writeln!(out, "format: 1")?;
+        writeln!(out, "crate_version: {}", env!("CARGO_PKG_VERSION"))?;
+        writeln!(out)?;
+        out.push_str(&self.properties().to_report_string());
+
+        writeln!(out)?;
+        writeln!(out, "[features]")?;
+        for (name, supported) in self.features().flags() {
+            writeln!(out, "{}: {}", name, supported)?;
+        }
+        writeln!(out, "sve_vector_bits: {}", self.features().sve_vector_bits)?;
+        writeln!(
+            out,
+            "clflush_line_size: {}",
+            self.features().clflush_line_size
+        )?;
+        writeln!(
+            out,
+            "monitor_line_size: {}..{}",
+            self.features().monitor_line_size_min,
+            self.features().monitor_line_size_max
+        )?;
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            writeln!(out)?;
+            writeln!(out, "[cpuid]")?;
+            for (leaf, subleaf) in snapshot_leaves() {
+                if let Some(cpuid) = crate::cpuid::cpuid(leaf, subleaf) {
+                    writeln!(
+                        out,
+                        "{:#010x}.{}: {:08x} {:08x} {:08x} {:08x}",
+                        leaf, subleaf, cpuid.eax, cpuid.ebx, cpuid.ecx, cpuid.edx
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// CPUID leaves and subleaves consulted during the detection.
+#[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))]
+fn snapshot_leaves() -> alloc::vec::Vec<(u32, u32)> {
+    // Enumerate cache subleaves until the null cache type.
+    let cache_subleaves = |leaf: u32| {
+        (0..16).take_while(move |&subleaf| {
+            crate::cpuid::cpuid(leaf, subleaf).is_some_and(|cpuid| cpuid.eax & 0x1F != 0)
+        })
+    };
+
+    let mut leaves = alloc::vec![(0, 0), (1, 0), (2, 0)];
+    leaves.extend(cache_subleaves(4).map(|subleaf| (4, subleaf)));
+    leaves.extend([
+        (5, 0),
+        (7, 0),
+        (7, 1),
+        (0xA, 0),
+        (0xB, 0),
+        (0xB, 1),
+        (0x16, 0),
+        (0x1A, 0),
+    ]);
+    // SGX capabilities and EPC sections, including the terminating invalid section.
+    let epc_sections = (2..64)
+        .take_while(|&subleaf| {
+            crate::cpuid::cpuid(0x12, subleaf).is_some_and(|cpuid| cpuid.eax & 0xF == 0x1)
+        })
+        .count() as u32;
+    leaves.extend((0..epc_sections + 3).map(|subleaf| (0x12, subleaf)));
+    // Deterministic address translation subleaves, the count is stored in subleaf `0`.
+    let tlb_subleaves = crate::cpuid::cpuid(0x18, 0).map_or(0, |cpuid| cpuid.eax.min(64) + 1);
+    leaves.extend((0..tlb_subleaves).map(|subleaf| (0x18, subleaf)));
+    leaves.extend([(0x1F, 0), (0x1F, 1), (0x40000000, 0)]);
+    leaves.extend((0x80000000..=0x80000008).map(|leaf| (leaf, 0)));
+    leaves.extend(cache_subleaves(0x8000001D).map(|subleaf| (0x8000001D, subleaf)));
+    leaves.extend([(0x8000001E, 0), (0x8000001F, 0)]);
+    leaves
+}
@@ -0,0 +1,154 @@
+use crate::device::PhysicalDevice;
+use crate::features::PhysicalDeviceFeatures;
+use crate::properties::ByteSize;
+use std::fmt;
+
+/// Minimum requirements a physical device has to meet.
+///
+/// Constraints which are `None` are not checked.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceRequirements {
+    /// Minimum number of physical cores.
+    pub min_physical_cores: Option<usize>,
+    /// Minimum number of logical cores.
+    pub min_logical_cores: Option<usize>,
+    /// Minimum total physical memory in bytes.
+    pub min_total_memory_bytes: Option<u64>,
+    /// Minimum size of the L3 cache in bytes.
+    pub min_l3_cache_bytes: Option<u32>,
+    /// Required features, all features set to `true` have to be supported.
+    pub features: PhysicalDeviceFeatures,
+}
+
+/// Requirement not met by a physical device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnmetRequirement {
+    /// Too few physical cores.
+    PhysicalCores {
+        /// Minimum number of physical cores.
+        required: usize,
+        /// Physical cores of the device.
+        available: usize,
+    },
+    /// Too few logical cores.
+    LogicalCores {
+        /// Minimum number of logical cores.
+        required: usize,
+        /// Logical cores of the device.
+        available: usize,
+    },
+    /// Too little physical memory.
+    TotalMemory {
+        /// Minimum total physical memory in bytes.
+        required: u64,
+        /// Total physical memory of the device in bytes.
+        available: u64,
+    },
+    /// L3 cache too small.
+    L3Cache {
+        /// Minimum size of the L3 cache in bytes.
+        required: u32,
+        /// Size of the L3 cache of the device in bytes.
+        available: u32,
+    },
+    /// Unsupported feature.
+    Feature(&'static str),
+}
+
+impl fmt::Display for UnmetRequirement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UnmetRequirement::PhysicalCores {
+                required,
+                available,
+            } => write!(
+                f,
+                "{} physical cores required, {} available",
+                required, available
+            ),
+            UnmetRequirement::LogicalCores {
+                required,
+                available,
+            } => write!(
+                f,
+                "{} logical cores required, {} available",
+                required, available
+            ),
+            UnmetRequirement::TotalMemory {
+                required,
+                available,
+            } => write!(
+                f,
+                "{} memory required, {} available",
+                ByteSize(*required),
+                ByteSize(*available)
+            ),
+            UnmetRequirement::L3Cache {
+                required,
+                available,
+            } => write!(
+                f,
+                "{} L3 cache required, {} available",
+                ByteSize(*required as _),
+                ByteSize(*available as _)
+            ),
+            UnmetRequirement::Feature(name) => write!(f, "{} not supported", name),
+        }
+    }
+}
+
+impl PhysicalDevice {
+    /// Returns if the device meets all requirements.
+    pub fn satisfies(&self, requirements: &DeviceRequirements) -> bool {
+        self.check(requirements).is_empty()
+    }
+
+    /// Check the device against the requirements, returning all unmet requirements.
+    pub fn check(&self, requirements: &DeviceRequirements) -> Vec<UnmetRequirement> {
+        let properties = self.properties();
+        let mut unmet = Vec::new();
+
+        if let Some(required) = requirements.min_physical_cores {
+            if properties.physical_cores < required {
+                unmet.push(UnmetRequirement::PhysicalCores {
+                    required,
+                    available: properties.physical_cores,
+                });
+            }
+        }
+        if let Some(required) = requirements.min_logical_cores {
+            if properties.logical_cores < required {
+                unmet.push(UnmetRequirement::LogicalCores {
+                    required,
+                    available: properties.logical_cores,
+                });
+            }
+        }
+        if let Some(required) = requirements.min_total_memory_bytes {
+            if properties.total_memory_bytes < required {
+                unmet.push(UnmetRequirement::TotalMemory {
+                    required,
+                    available: properties.total_memory_bytes,
+                });
+            }
+        }
+        if let Some(required) = requirements.min_l3_cache_bytes {
+            if properties.l3_cache.size < required {
+                unmet.push(UnmetRequirement::L3Cache {
+                    required,
+                    available: properties.l3_cache.size,
+                });
+            }
+        }
+
+        let required = requirements.features.flags();
+        let supported = self.features().flags();
+        for ((name, required), (_, supported)) in required.into_iter().zip(supported) {
+            if required && !supported {
+                unmet.push(UnmetRequirement::Feature(name));
+            }
+        }
+
+        unmet
+    }
+}
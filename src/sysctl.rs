@@ -0,0 +1,73 @@
+//! Helpers for reading device information via `sysctlbyname` (macOS, FreeBSD).
+
+use std::ffi::CString;
+use std::ptr;
+
+/// Read an integer value, accepting both 32 and 64 bit results.
+pub(crate) fn read_u64(name: &str) -> Option<u64> {
+    let name = CString::new(name).ok()?;
+    let mut value = [0u8; 8];
+    let mut length = value.len();
+
+    let result = unsafe {
+        libc::sysctlbyname(
+            name.as_ptr(),
+            value.as_mut_ptr() as *mut _,
+            &mut length,
+            ptr::null_mut(),
+            0,
+        )
+    };
+    if result != 0 {
+        trace!(?name, "sysctl unavailable");
+        return None;
+    }
+
+    let value = match length {
+        4 => Some(u32::from_ne_bytes([value[0], value[1], value[2], value[3]]) as u64),
+        8 => Some(u64::from_ne_bytes(value)),
+        _ => None,
+    };
+    trace!(?name, ?value, "sysctl");
+    value
+}
+
+/// Read a string value.
+#[cfg(target_arch = "aarch64")]
+pub(crate) fn read_string(name: &str) -> Option<String> {
+    let name = CString::new(name).ok()?;
+    let mut length = 0;
+
+    let result = unsafe {
+        libc::sysctlbyname(
+            name.as_ptr(),
+            ptr::null_mut(),
+            &mut length,
+            ptr::null_mut(),
+            0,
+        )
+    };
+    if result != 0 {
+        return None;
+    }
+
+    let mut value = vec![0u8; length];
+    let result = unsafe {
+        libc::sysctlbyname(
+            name.as_ptr(),
+            value.as_mut_ptr() as *mut _,
+            &mut length,
+            ptr::null_mut(),
+            0,
+        )
+    };
+    if result != 0 {
+        return None;
+    }
+
+    value.truncate(length);
+    let value = String::from_utf8(value).ok()?;
+    let value = value.trim_end_matches('\0').trim().to_owned();
+    trace!(?name, %value, "sysctl");
+    Some(value)
+}
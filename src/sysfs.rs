@@ -0,0 +1,607 @@
+//! Helpers for reading device information from the Linux `sysfs` and `procfs`,
+//! including the cpu constraints (affinity, cgroup quota) of the current process.
+
+use crate::properties::{
+    insert_cache, CacheKind, CacheWritePolicy, CoreAffinity, NumaNode, Package,
+    PhysicalDeviceCacheProperties,
+};
+use crate::topology::CacheInstance;
+use std::fs;
+use std::path::Path;
+
+fn read_file<P: AsRef<Path>>(path: P) -> Option<String> {
+    let content = fs::read_to_string(&path).ok();
+    trace!(
+        path = %path.as_ref().display(),
+        bytes = content.as_ref().map(String::len),
+        "read file"
+    );
+    content
+}
+
+fn read_value(path: &Path) -> Option<String> {
+    let value = fs::read_to_string(path).ok().map(|v| v.trim().to_owned());
+    trace!(path = %path.display(), value = ?value, "read value");
+    value
+}
+
+/// Parse a cpu list like `0-3,8-11` into the contained cpu indices.
+pub(crate) fn parse_cpu_list(list: &str) -> Vec<usize> {
+    let mut cpus = Vec::new();
+    for range in list
+        .split(',')
+        .map(str::trim)
+        .filter(|range| !range.is_empty())
+    {
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (start.parse::<usize>(), end.parse::<usize>()),
+            None => (range.parse::<usize>(), range.parse::<usize>()),
+        };
+        if let (Ok(start), Ok(end)) = (start, end) {
+            cpus.extend(start..=end);
+        }
+    }
+    cpus
+}
+
+/// Parse a size value like `32K` or `8M` into bytes.
+pub(crate) fn parse_size(value: &str) -> Option<u32> {
+    let (digits, scale) = match value.chars().last()? {
+        'K' => (&value[..value.len() - 1], 1024),
+        'M' => (&value[..value.len() - 1], 1024 * 1024),
+        'G' => (&value[..value.len() - 1], 1024 * 1024 * 1024),
+        _ => (value, 1),
+    };
+
+    digits.parse::<u32>().ok()?.checked_mul(scale)
+}
+
+/// Parse the `write_policy` of a cache index, e.g. `WriteBack`.
+fn parse_write_policy(policy: &str) -> CacheWritePolicy {
+    match policy.trim() {
+        "WriteBack" => CacheWritePolicy::WriteBack,
+        "WriteThrough" => CacheWritePolicy::WriteThrough,
+        _ => CacheWritePolicy::Unknown,
+    }
+}
+
+/// Read the caches of a cpu cache directory like `/sys/devices/system/cpu/cpu0/cache`.
+///
+/// Returns the level, kind, properties and sharing logical cores of each `index*` entry.
+fn read_caches(dir: &Path) -> Vec<CacheInstance> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut caches = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_index = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("index"));
+        if !is_index {
+            continue;
+        }
+
+        let level = match read_value(&path.join("level")).and_then(|level| level.parse().ok()) {
+            Some(level) => level,
+            None => continue,
+        };
+        let kind = match read_value(&path.join("type")).as_deref() {
+            Some("Data") => CacheKind::Data,
+            Some("Instruction") => CacheKind::Instruction,
+            Some("Unified") => CacheKind::Unified,
+            _ => continue,
+        };
+
+        let read_u32 = |name: &str| {
+            read_value(&path.join(name))
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(0)
+        };
+        let logical_cores = read_value(&path.join("shared_cpu_list"))
+            .map(|cpus| parse_cpu_list(&cpus))
+            .unwrap_or_default();
+
+        let properties = PhysicalDeviceCacheProperties {
+            size: read_value(&path.join("size"))
+                .and_then(|size| parse_size(&size))
+                .unwrap_or(0),
+            line_size: read_u32("coherency_line_size"),
+            associativity: read_u32("ways_of_associativity"),
+            sets: read_u32("number_of_sets"),
+            write_policy: read_value(&path.join("write_policy"))
+                .map_or(CacheWritePolicy::Unknown, |policy| {
+                    parse_write_policy(&policy)
+                }),
+            shared_by_cores: logical_cores.len() as u32,
+            ..Default::default()
+        };
+
+        caches.push(CacheInstance {
+            level,
+            kind,
+            properties,
+            logical_cores,
+        });
+    }
+    caches
+}
+
+/// Read the caches of `cpu0` as `(l1_data, l1_instruction, l2, l3, l4, l1_unified)`.
+///
+/// Returns `None` if no cache information is exposed.
+pub(crate) fn caches() -> Option<(
+    PhysicalDeviceCacheProperties,
+    PhysicalDeviceCacheProperties,
+    PhysicalDeviceCacheProperties,
+    PhysicalDeviceCacheProperties,
+    PhysicalDeviceCacheProperties,
+    PhysicalDeviceCacheProperties,
+)> {
+    let mut caches: [PhysicalDeviceCacheProperties; 6] = Default::default();
+    let mut found = false;
+
+    for cache in read_caches(Path::new("/sys/devices/system/cpu/cpu0/cache")) {
+        insert_cache(
+            &mut caches,
+            cache.kind,
+            cache.level as u32,
+            cache.properties,
+        );
+        found = true;
+    }
+
+    let [l1_cache_data, l1_cache_instruction, l2_cache, l3_cache, l4_cache, l1_cache_unified] =
+        caches;
+    if found {
+        Some((
+            l1_cache_data,
+            l1_cache_instruction,
+            l2_cache,
+            l3_cache,
+            l4_cache,
+            l1_cache_unified,
+        ))
+    } else {
+        None
+    }
+}
+
+/// Read the distinct cache instances of all cpus.
+pub(crate) fn cache_instances() -> Vec<CacheInstance> {
+    let entries = match fs::read_dir("/sys/devices/system/cpu") {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut instances = Vec::<CacheInstance>::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let is_cpu = name
+            .to_str()
+            .and_then(|name| name.strip_prefix("cpu"))
+            .is_some_and(|index| index.parse::<usize>().is_ok());
+        if !is_cpu {
+            continue;
+        }
+
+        for cache in read_caches(&entry.path().join("cache")) {
+            let known = instances.iter().any(|instance| {
+                instance.level == cache.level
+                    && instance.kind == cache.kind
+                    && instance.logical_cores == cache.logical_cores
+            });
+            if !known {
+                instances.push(cache);
+            }
+        }
+    }
+    instances
+}
+
+/// Read a `cpufreq` value of `cpu0` in kHz.
+pub(crate) fn cpufreq_khz(name: &str) -> Option<u32> {
+    read_value(&Path::new("/sys/devices/system/cpu/cpu0/cpufreq").join(name))?
+        .parse()
+        .ok()
+}
+
+/// Read whether turbo boost is enabled.
+///
+/// Checks `intel_pstate/no_turbo`, the `cpufreq/boost` knob (e.g. `acpi-cpufreq` on AMD)
+/// and the turbo disable bit of `IA32_MISC_ENABLE` via `/dev/cpu/0/msr` (requires root).
+pub(crate) fn turbo_enabled() -> Option<bool> {
+    let cpu = Path::new("/sys/devices/system/cpu");
+    if let Some(no_turbo) = read_value(&cpu.join("intel_pstate/no_turbo")) {
+        return Some(no_turbo == "0");
+    }
+    if let Some(boost) = read_value(&cpu.join("cpufreq/boost")) {
+        return Some(boost == "1");
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        const IA32_MISC_ENABLE: u64 = 0x1A0;
+
+        Some(read_msr(IA32_MISC_ENABLE)? & (1 << 38) == 0)
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    {
+        None
+    }
+}
+
+/// Read a model specific register of `cpu0` via `/dev/cpu/0/msr` (requires root).
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn read_msr(register: u64) -> Option<u64> {
+    use std::os::unix::fs::FileExt;
+
+    let msr = fs::File::open("/dev/cpu/0/msr").ok()?;
+    let mut value = [0u8; 8];
+    msr.read_exact_at(&mut value, register).ok()?;
+    let value = u64::from_le_bytes(value);
+    trace!(
+        register = format_args!("{:#x}", register),
+        value = format_args!("{:#x}", value),
+        "read msr"
+    );
+    Some(value)
+}
+
+/// Read the thermal design power of the first package in watts.
+///
+/// Checks the maximum power of the RAPL package domain, then the thermal spec power
+/// of `MSR_PKG_POWER_INFO` (Intel, requires root).
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub(crate) fn tdp_watts() -> Option<u32> {
+    const MSR_RAPL_POWER_UNIT: u64 = 0x606;
+    const MSR_PKG_POWER_INFO: u64 = 0x614;
+
+    let rapl = Path::new("/sys/class/powercap/intel-rapl:0/constraint_0_max_power_uw");
+    if let Some(microwatts) = read_value(rapl).and_then(|uw| uw.parse::<u64>().ok()) {
+        if microwatts > 0 {
+            return Some((microwatts / 1_000_000) as u32);
+        }
+    }
+
+    // Power is given in units of 1 / 2^PU watts.
+    let power_unit = read_msr(MSR_RAPL_POWER_UNIT)? & 0xF;
+    let thermal_spec_power = read_msr(MSR_PKG_POWER_INFO)? & 0x7FFF;
+    Some((thermal_spec_power >> power_unit) as u32).filter(|&watts| watts > 0)
+}
+
+/// Read the current frequency of the first processor from `/proc/cpuinfo` in MHz.
+pub(crate) fn cpuinfo_frequency_mhz() -> Option<u32> {
+    let cpuinfo = read_file("/proc/cpuinfo")?;
+    let line = cpuinfo.lines().find(|line| line.starts_with("cpu MHz"))?;
+    let (_, value) = line.split_once(':')?;
+    let value = value.trim().parse::<f32>().ok()?;
+
+    Some(value.round() as u32)
+}
+
+/// Read the feature flags of the first processor from `/proc/cpuinfo`.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub(crate) fn cpuinfo_flags() -> Option<Vec<String>> {
+    let cpuinfo = read_file("/proc/cpuinfo")?;
+    let line = cpuinfo.lines().find(|line| line.starts_with("flags"))?;
+    let (_, value) = line.split_once(':')?;
+
+    Some(value.split_whitespace().map(String::from).collect())
+}
+
+/// Read the NUMA nodes and their cpus.
+///
+/// Returns an empty list if no NUMA information is exposed.
+pub(crate) fn numa_nodes() -> Vec<NumaNode> {
+    let entries = match fs::read_dir("/sys/devices/system/node") {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut nodes = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let node_id = name.to_str()?.strip_prefix("node")?.parse().ok()?;
+            let cpus = read_value(&entry.path().join("cpulist"))?;
+
+            Some(NumaNode {
+                node_id,
+                logical_cores: parse_cpu_list(&cpus),
+            })
+        })
+        .collect::<Vec<_>>();
+    nodes.sort_by_key(|node| node.node_id);
+    nodes
+}
+
+/// Read the logical cores of each physical core from the `thread_siblings_list` topology.
+///
+/// Returns an empty list if no topology information is exposed.
+pub(crate) fn core_affinity() -> Vec<CoreAffinity> {
+    let entries = match fs::read_dir("/sys/devices/system/cpu") {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut cores = Vec::<CoreAffinity>::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let is_cpu = name
+            .to_str()
+            .and_then(|name| name.strip_prefix("cpu"))
+            .is_some_and(|index| index.parse::<usize>().is_ok());
+        if !is_cpu {
+            continue;
+        }
+
+        let siblings = match read_value(&entry.path().join("topology/thread_siblings_list")) {
+            Some(siblings) => parse_cpu_list(&siblings),
+            None => continue,
+        };
+        if !siblings.is_empty() && !cores.iter().any(|core| core.logical_cores == siblings) {
+            cores.push(CoreAffinity {
+                logical_cores: siblings,
+            });
+        }
+    }
+    cores.sort();
+    cores
+}
+
+/// Read the total and available memory in bytes from `/proc/meminfo`.
+pub(crate) fn memory() -> Option<(u64, u64)> {
+    let meminfo = read_file("/proc/meminfo")?;
+    let value = |key: &str| -> Option<u64> {
+        let line = meminfo.lines().find(|line| line.starts_with(key))?;
+        let (_, value) = line.split_once(':')?;
+        let kib = value
+            .trim()
+            .trim_end_matches("kB")
+            .trim()
+            .parse::<u64>()
+            .ok()?;
+        Some(kib * 1024)
+    };
+
+    Some((value("MemTotal")?, value("MemAvailable").unwrap_or(0)))
+}
+
+/// Read the huge page sizes in bytes enabled by the kernel, in ascending order.
+///
+/// Sizes are read from `/sys/kernel/mm/hugepages`, falling back to `Hugepagesize`
+/// in `/proc/meminfo`. Returns `None` if neither is available.
+pub(crate) fn huge_page_sizes() -> Option<Vec<u64>> {
+    let mut sizes = fs::read_dir("/sys/kernel/mm/hugepages")
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| {
+                    let name = entry.file_name();
+                    let kib = name
+                        .to_str()?
+                        .strip_prefix("hugepages-")?
+                        .strip_suffix("kB")?
+                        .parse::<u64>()
+                        .ok()?;
+                    Some(kib * 1024)
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    if sizes.is_empty() {
+        let meminfo = read_file("/proc/meminfo")?;
+        let line = meminfo
+            .lines()
+            .find(|line| line.starts_with("Hugepagesize"))?;
+        let (_, value) = line.split_once(':')?;
+        let kib = value
+            .trim()
+            .trim_end_matches("kB")
+            .trim()
+            .parse::<u64>()
+            .ok()?;
+        sizes.push(kib * 1024);
+    }
+
+    sizes.sort_unstable();
+    Some(sizes)
+}
+
+/// Query the cpus the current process is allowed to run on via `sched_getaffinity`.
+pub(crate) fn allowed_cpus() -> Option<Vec<usize>> {
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    let result =
+        unsafe { libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) };
+    if result != 0 {
+        return None;
+    }
+
+    let cpus = (0..libc::CPU_SETSIZE as usize)
+        .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+        .collect::<Vec<_>>();
+    trace!(?cpus, "sched_getaffinity");
+    Some(cpus)
+}
+
+/// Read the number of online cpus from `/sys/devices/system/cpu/online`.
+pub(crate) fn online_cpus() -> Option<usize> {
+    let online = read_value(Path::new("/sys/devices/system/cpu/online"))?;
+    Some(parse_cpu_list(&online).len()).filter(|&cpus| cpus > 0)
+}
+
+/// Read the cgroup v2 `cpu.max` quota of the current process in cpus, rounded up.
+///
+/// The quota is the smallest one of the cgroup and its ancestors.
+/// Returns `None` if no quota is set or cgroup v2 isn't available.
+pub(crate) fn cgroup_cpu_quota() -> Option<usize> {
+    let cgroup = read_file("/proc/self/cgroup")?;
+    let path = cgroup
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))?
+        .trim();
+    cgroup_hierarchy_quota(Path::new("/sys/fs/cgroup"), path)
+}
+
+/// Smallest `cpu.max` quota of the cgroup `path` and its ancestors below `root`.
+fn cgroup_hierarchy_quota(root: &Path, path: &str) -> Option<usize> {
+    Path::new(path.trim_start_matches('/'))
+        .ancestors()
+        .filter_map(|cgroup| read_value(&root.join(cgroup).join("cpu.max")))
+        .filter_map(|cpu_max| parse_cpu_max(&cpu_max))
+        .min()
+}
+
+/// Parse a cgroup v2 `cpu.max` value like `200000 100000` (quota and period) into cpus.
+fn parse_cpu_max(cpu_max: &str) -> Option<usize> {
+    let mut values = cpu_max.split_whitespace();
+    let quota = values.next()?.parse::<u64>().ok()?;
+    let period = values
+        .next()
+        .map_or(Some(100_000), |period| period.parse::<u64>().ok())?;
+    if period == 0 {
+        return None;
+    }
+
+    Some(quota.div_ceil(period).max(1) as usize)
+}
+
+/// Read the physical packages from `/proc/cpuinfo`.
+///
+/// Returns an empty list if no package ids are exposed (e.g. on most ARM systems).
+pub(crate) fn packages() -> Vec<Package> {
+    match read_file("/proc/cpuinfo") {
+        Some(cpuinfo) => parse_cpuinfo_packages(&cpuinfo),
+        None => Vec::new(),
+    }
+}
+
+fn parse_cpuinfo_packages(cpuinfo: &str) -> Vec<Package> {
+    let mut packages = Vec::<(u32, Package, Vec<u32>)>::new();
+
+    for processor in cpuinfo.split("\n\n") {
+        let field = |key: &str| -> Option<u32> {
+            let line = processor
+                .lines()
+                .find(|line| line.split(':').next().map(str::trim) == Some(key))?;
+            line.split_once(':')?.1.trim().parse().ok()
+        };
+
+        let (cpu, package_id) = match (field("processor"), field("physical id")) {
+            (Some(cpu), Some(package_id)) => (cpu as usize, package_id),
+            _ => continue,
+        };
+        let core_id = field("core id").unwrap_or(cpu as u32);
+
+        let index = match packages.iter().position(|(id, _, _)| *id == package_id) {
+            Some(index) => index,
+            None => {
+                packages.push((
+                    package_id,
+                    Package {
+                        logical_cores: Vec::new(),
+                        physical_cores: 0,
+                    },
+                    Vec::new(),
+                ));
+                packages.len() - 1
+            }
+        };
+
+        let (_, package, core_ids) = &mut packages[index];
+        package.logical_cores.push(cpu);
+        if !core_ids.contains(&core_id) {
+            core_ids.push(core_id);
+            package.physical_cores += 1;
+        }
+    }
+
+    packages.sort_by_key(|(id, _, _)| *id);
+    packages
+        .into_iter()
+        .map(|(_, package, _)| package)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_list() {
+        assert_eq!(parse_cpu_list("0-3,8-9,12\n"), [0, 1, 2, 3, 8, 9, 12]);
+        assert_eq!(parse_cpu_list("5"), [5]);
+        assert!(parse_cpu_list("").is_empty());
+    }
+
+    #[test]
+    fn cpu_max() {
+        assert_eq!(parse_cpu_max("max 100000"), None);
+        assert_eq!(parse_cpu_max("200000 100000\n"), Some(2));
+        assert_eq!(parse_cpu_max("150000 100000"), Some(2));
+        assert_eq!(parse_cpu_max("10000 100000"), Some(1));
+        assert_eq!(parse_cpu_max("50000"), Some(1));
+    }
+
+    #[test]
+    fn cgroup_hierarchy() {
+        let root = std::env::temp_dir().join("norse-cgroup-hierarchy");
+        let leaf = root.join("parent/child");
+        fs::create_dir_all(&leaf).unwrap();
+        fs::write(root.join("parent/cpu.max"), "100000 100000\n").unwrap();
+        fs::write(leaf.join("cpu.max"), "max 100000\n").unwrap();
+
+        assert_eq!(cgroup_hierarchy_quota(&root, "/parent/child"), Some(1));
+        assert_eq!(cgroup_hierarchy_quota(&root, "/"), None);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn size() {
+        assert_eq!(parse_size("32K"), Some(32 * 1024));
+        assert_eq!(parse_size("8M"), Some(8 * 1024 * 1024));
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("K"), None);
+    }
+
+    #[test]
+    fn write_policy() {
+        assert_eq!(
+            parse_write_policy("WriteBack\n"),
+            CacheWritePolicy::WriteBack
+        );
+        assert_eq!(
+            parse_write_policy("WriteThrough"),
+            CacheWritePolicy::WriteThrough
+        );
+        assert_eq!(parse_write_policy(""), CacheWritePolicy::Unknown);
+    }
+
+    #[test]
+    fn cpuinfo_packages() {
+        let cpuinfo = "processor\t: 0\nphysical id\t: 0\ncore id\t\t: 0\n\n\
+                       processor\t: 1\nphysical id\t: 1\ncore id\t\t: 0\n\n\
+                       processor\t: 2\nphysical id\t: 0\ncore id\t\t: 0\n\n\
+                       processor\t: 3\nphysical id\t: 1\ncore id\t\t: 1\n";
+
+        assert_eq!(
+            parse_cpuinfo_packages(cpuinfo),
+            [
+                Package {
+                    logical_cores: vec![0, 2],
+                    physical_cores: 1,
+                },
+                Package {
+                    logical_cores: vec![1, 3],
+                    physical_cores: 2,
+                },
+            ]
+        );
+    }
+}
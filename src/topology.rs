@@ -0,0 +1,287 @@
+use crate::device::PhysicalDevice;
+use crate::properties::{CacheKind, CoreAffinity, NumaNode, PhysicalDeviceCacheProperties};
+
+/// Hierarchical topology of a device: packages → NUMA nodes → cores → threads.
+///
+/// Caches are attached to the smallest level containing all logical cores sharing them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Topology {
+    /// Physical packages (sockets).
+    pub packages: Vec<TopologyPackage>,
+}
+
+/// Physical package of the topology.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TopologyPackage {
+    /// OS indices of the logical cores belonging to the package.
+    pub logical_cores: Vec<usize>,
+    /// NUMA nodes of the package.
+    pub numa_nodes: Vec<TopologyNumaNode>,
+    /// Caches shared across NUMA nodes of the package.
+    pub caches: Vec<CacheInstance>,
+}
+
+/// NUMA node of the topology.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TopologyNumaNode {
+    /// OS index of the node.
+    pub node_id: u32,
+    /// Physical cores of the node.
+    pub cores: Vec<TopologyCore>,
+    /// Caches shared across cores of the node (e.g. L3 of a core complex).
+    pub caches: Vec<CacheInstance>,
+}
+
+/// Physical core of the topology.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TopologyCore {
+    /// OS indices of the hardware threads (logical cores) of the core.
+    pub logical_cores: Vec<usize>,
+    /// Caches private to the core.
+    pub caches: Vec<CacheInstance>,
+}
+
+/// Single instance of a cache and the logical cores sharing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheInstance {
+    /// Cache level, starting at `1`.
+    pub level: u8,
+    /// Kind of cached content.
+    pub kind: CacheKind,
+    /// Properties of the cache.
+    pub properties: PhysicalDeviceCacheProperties,
+    /// OS indices of the logical cores sharing the cache.
+    pub logical_cores: Vec<usize>,
+}
+
+impl Topology {
+    /// Build the topology tree from the flat package, node, core and cache lists.
+    ///
+    /// Only logical cores of the NUMA nodes are considered. Logical cores without a core
+    /// record form single threaded cores, missing packages a single package.
+    pub(crate) fn build(
+        packages: Vec<Vec<usize>>,
+        numa_nodes: &[NumaNode],
+        cores: Vec<CoreAffinity>,
+        caches: Vec<CacheInstance>,
+    ) -> Topology {
+        let mut logical_cores = numa_nodes
+            .iter()
+            .flat_map(|node| node.logical_cores.iter().copied())
+            .collect::<Vec<_>>();
+        logical_cores.sort_unstable();
+        logical_cores.dedup();
+
+        let mut packages = packages
+            .into_iter()
+            .map(|package| {
+                package
+                    .into_iter()
+                    .filter(|cpu| logical_cores.contains(cpu))
+                    .collect::<Vec<_>>()
+            })
+            .filter(|package| !package.is_empty())
+            .collect::<Vec<_>>();
+        if packages.is_empty() {
+            packages.push(logical_cores.clone());
+        }
+
+        let mut packages = packages
+            .into_iter()
+            .map(|package| {
+                let numa_nodes = numa_nodes
+                    .iter()
+                    .map(|node| {
+                        let node_cores = node
+                            .logical_cores
+                            .iter()
+                            .copied()
+                            .filter(|cpu| package.contains(cpu))
+                            .collect::<Vec<_>>();
+                        (node.node_id, node_cores)
+                    })
+                    .filter(|(_, node_cores)| !node_cores.is_empty())
+                    .map(|(node_id, node_cores)| TopologyNumaNode {
+                        node_id,
+                        cores: node_topology_cores(&node_cores, &cores),
+                        caches: Vec::new(),
+                    })
+                    .collect();
+
+                TopologyPackage {
+                    logical_cores: package,
+                    numa_nodes,
+                    caches: Vec::new(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        for cache in caches {
+            attach_cache(&mut packages, cache);
+        }
+
+        Topology { packages }
+    }
+
+    /// Iterate over all cores of the topology.
+    pub fn cores(&self) -> impl Iterator<Item = &TopologyCore> {
+        self.packages
+            .iter()
+            .flat_map(|package| package.numa_nodes.iter())
+            .flat_map(|node| node.cores.iter())
+    }
+}
+
+/// Group the logical cores of a node into physical cores.
+fn node_topology_cores(node_cores: &[usize], cores: &[CoreAffinity]) -> Vec<TopologyCore> {
+    let mut topology_cores = Vec::<TopologyCore>::new();
+    for &cpu in node_cores {
+        if topology_cores
+            .iter()
+            .any(|core| core.logical_cores.contains(&cpu))
+        {
+            continue;
+        }
+
+        let logical_cores = cores
+            .iter()
+            .find(|core| core.logical_cores.contains(&cpu))
+            .map(|core| {
+                core.logical_cores
+                    .iter()
+                    .copied()
+                    .filter(|cpu| node_cores.contains(cpu))
+                    .collect()
+            })
+            .unwrap_or_else(|| vec![cpu]);
+        topology_cores.push(TopologyCore {
+            logical_cores,
+            caches: Vec::new(),
+        });
+    }
+    topology_cores
+}
+
+/// Attach a cache to the smallest level containing all of its logical cores.
+fn attach_cache(packages: &mut [TopologyPackage], cache: CacheInstance) {
+    let covers = |cpus: &[usize]| cache.logical_cores.iter().all(|cpu| cpus.contains(cpu));
+
+    let package = match packages.iter_mut().find(|package| {
+        cache
+            .logical_cores
+            .iter()
+            .any(|cpu| package.logical_cores.contains(cpu))
+    }) {
+        Some(package) => package,
+        None => return,
+    };
+
+    for node in &mut package.numa_nodes {
+        if let Some(core) = node
+            .cores
+            .iter_mut()
+            .find(|core| covers(&core.logical_cores))
+        {
+            core.caches.push(cache);
+            return;
+        }
+
+        let node_cores = node
+            .cores
+            .iter()
+            .flat_map(|core| core.logical_cores.iter().copied())
+            .collect::<Vec<_>>();
+        if covers(&node_cores) {
+            node.caches.push(cache);
+            return;
+        }
+    }
+
+    package.caches.push(cache);
+}
+
+impl PhysicalDevice {
+    /// Query the topology tree of the device with the caches attached.
+    pub fn topology(&self) -> Topology {
+        self.properties().topology()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(level: u8, kind: CacheKind, logical_cores: Vec<usize>) -> CacheInstance {
+        CacheInstance {
+            level,
+            kind,
+            properties: PhysicalDeviceCacheProperties::default(),
+            logical_cores,
+        }
+    }
+
+    #[test]
+    fn tree() {
+        // Two packages with a NUMA node each, two SMT cores per package and
+        // one core complex L3 per package.
+        let numa_nodes = [
+            NumaNode {
+                node_id: 0,
+                logical_cores: vec![0, 1, 2, 3],
+            },
+            NumaNode {
+                node_id: 1,
+                logical_cores: vec![4, 5, 6, 7],
+            },
+        ];
+        let cores = (0..4)
+            .map(|core| CoreAffinity {
+                logical_cores: vec![core * 2, core * 2 + 1],
+            })
+            .collect();
+        let caches = vec![
+            cache(1, CacheKind::Data, vec![0, 1]),
+            cache(2, CacheKind::Unified, vec![6, 7]),
+            cache(3, CacheKind::Unified, vec![0, 1, 2, 3]),
+            cache(3, CacheKind::Unified, vec![4, 5, 6, 7]),
+        ];
+        let topology = Topology::build(
+            vec![vec![0, 1, 2, 3], vec![4, 5, 6, 7]],
+            &numa_nodes,
+            cores,
+            caches,
+        );
+
+        assert_eq!(topology.packages.len(), 2);
+        assert_eq!(topology.cores().count(), 4);
+
+        let package = &topology.packages[1];
+        assert_eq!(package.logical_cores, [4, 5, 6, 7]);
+        assert_eq!(package.numa_nodes[0].node_id, 1);
+        assert_eq!(package.numa_nodes[0].caches[0].level, 3);
+        assert_eq!(package.numa_nodes[0].cores[1].logical_cores, [6, 7]);
+        assert_eq!(package.numa_nodes[0].cores[1].caches[0].level, 2);
+        assert!(package.caches.is_empty());
+
+        let core = &topology.packages[0].numa_nodes[0].cores[0];
+        assert_eq!(core.caches[0].kind, CacheKind::Data);
+    }
+
+    #[test]
+    fn missing_records() {
+        let numa_nodes = [NumaNode {
+            node_id: 0,
+            logical_cores: vec![0, 1],
+        }];
+        let topology = Topology::build(
+            Vec::new(),
+            &numa_nodes,
+            Vec::new(),
+            vec![cache(3, CacheKind::Unified, vec![0, 1])],
+        );
+
+        assert_eq!(topology.packages.len(), 1);
+        assert_eq!(topology.cores().count(), 2);
+        assert_eq!(topology.packages[0].numa_nodes[0].caches.len(), 1);
+    }
+}
@@ -0,0 +1,444 @@
+//! Helpers for querying device information via `GetLogicalProcessorInformation`.
+
+use crate::error::DeviceError;
+use crate::properties::{
+    insert_cache, CacheKind, CoreAffinity, NumaNode, Package, PhysicalDeviceCacheProperties,
+};
+use crate::topology::CacheInstance;
+use winapi::shared::winerror::ERROR_INSUFFICIENT_BUFFER;
+use winapi::um::processthreadsapi::GetCurrentProcess;
+use winapi::um::sysinfoapi::*;
+use winapi::um::winnt::*;
+use winapi::um::wow64apiset::IsWow64Process2;
+
+/// Attempts to query the records, the required buffer size may grow between the calls
+/// while processors are hot-added.
+const MAX_ATTEMPTS: usize = 8;
+
+/// Query the logical processor information records of the system.
+#[allow(unused_variables)]
+pub(crate) fn logical_processor_information(
+) -> Result<Vec<SYSTEM_LOGICAL_PROCESSOR_INFORMATION>, DeviceError> {
+    let info_size = std::mem::size_of::<SYSTEM_LOGICAL_PROCESSOR_INFORMATION>() as u32;
+
+    // Size of the buffer in bytes, updated to the required size on failure.
+    let mut length = 0;
+    for attempt in 0..MAX_ATTEMPTS {
+        let num_infos = length / info_size;
+        let mut infos = Vec::with_capacity(num_infos as _);
+        let mut buffer_length = num_infos * info_size;
+
+        if unsafe { GetLogicalProcessorInformation(infos.as_mut_ptr(), &mut buffer_length) } != 0 {
+            if !buffer_length.is_multiple_of(info_size) || buffer_length / info_size > num_infos {
+                return Err(DeviceError::UnexpectedLayout);
+            }
+            unsafe {
+                infos.set_len((buffer_length / info_size) as _);
+            }
+            debug!(records = infos.len(), "logical processor information");
+            return Ok(infos);
+        }
+
+        let code = std::io::Error::last_os_error().raw_os_error().unwrap_or(0);
+        if code != ERROR_INSUFFICIENT_BUFFER as i32 {
+            return Err(DeviceError::SyscallFailed(code));
+        }
+        if !buffer_length.is_multiple_of(info_size) || buffer_length <= length {
+            return Err(DeviceError::UnexpectedLayout);
+        }
+        trace!(
+            attempt,
+            length = buffer_length,
+            "logical processor information buffer"
+        );
+        length = buffer_length;
+    }
+
+    Err(DeviceError::SyscallFailed(ERROR_INSUFFICIENT_BUFFER as i32))
+}
+
+/// Query the number of active logical processors across all processor groups.
+pub(crate) fn active_processor_count() -> usize {
+    unsafe { winapi::um::winbase::GetActiveProcessorCount(ALL_PROCESSOR_GROUPS) as usize }
+}
+
+/// Returns if the x86_64 process is emulated on an ARM64 machine.
+///
+/// `IsWow64Process2` reports the native machine of the system, which differs from
+/// the process architecture when running under the emulation of Windows on ARM.
+pub(crate) fn is_emulated() -> bool {
+    let mut process_machine = 0;
+    let mut native_machine = 0;
+    let result = unsafe {
+        IsWow64Process2(
+            GetCurrentProcess(),
+            &mut process_machine,
+            &mut native_machine,
+        )
+    };
+    // Not available before Windows 10.
+    result != 0 && native_machine == IMAGE_FILE_MACHINE_ARM64
+}
+
+/// Query the total and available physical memory in bytes.
+pub(crate) fn memory() -> Option<(u64, u64)> {
+    let mut status: MEMORYSTATUSEX = unsafe { std::mem::zeroed() };
+    status.dwLength = std::mem::size_of::<MEMORYSTATUSEX>() as u32;
+    if unsafe { GlobalMemoryStatusEx(&mut status) } == 0 {
+        return None;
+    }
+
+    Some((status.ullTotalPhys, status.ullAvailPhys))
+}
+
+/// Core and cache information aggregated from the logical processor information records.
+pub(crate) struct ProcessorInformation {
+    pub logical_cores: usize,
+    pub physical_cores: usize,
+    pub l1_cache_data: PhysicalDeviceCacheProperties,
+    pub l1_cache_instruction: PhysicalDeviceCacheProperties,
+    pub l1_cache_unified: PhysicalDeviceCacheProperties,
+    pub l2_cache: PhysicalDeviceCacheProperties,
+    pub l3_cache: PhysicalDeviceCacheProperties,
+    pub l4_cache: PhysicalDeviceCacheProperties,
+    pub numa_nodes: Vec<NumaNode>,
+    pub packages: Vec<Package>,
+    pub cores: Vec<CoreAffinity>,
+    pub caches: Vec<CacheInstance>,
+}
+
+/// Indices of the logical processors set in an affinity mask.
+fn mask_indices(mask: usize) -> Vec<usize> {
+    (0..usize::BITS as usize)
+        .filter(|i| mask & (1 << i) != 0)
+        .collect()
+}
+
+impl ProcessorInformation {
+    pub fn parse(infos: &[SYSTEM_LOGICAL_PROCESSOR_INFORMATION]) -> Self {
+        // Core caches are averaged over all cores, the L3 and L4 are reported per package.
+        let mut logical_cores = 0;
+        let mut physical_cores = 0;
+        // Aggregated caches per level and kind, stored in the slots below.
+        let mut aggregates = Vec::<(u8, CacheKind, PhysicalDeviceCacheProperties)>::new();
+        let mut numa_nodes = Vec::new();
+        let mut packages = Vec::new();
+        let mut cores = Vec::new();
+        let mut caches = Vec::<CacheInstance>::new();
+        let mut l3_instances = Vec::<(CacheKind, usize, u32)>::new();
+
+        #[allow(non_upper_case_globals)]
+        for info in infos {
+            match info.Relationship {
+                RelationProcessorCore => {
+                    // The core mask contains all logical processors (SMT siblings) of the core.
+                    physical_cores += 1;
+                    logical_cores += info.ProcessorMask.count_ones() as usize;
+                    cores.push(info.ProcessorMask);
+                }
+                RelationProcessorPackage => {
+                    packages.push(info.ProcessorMask);
+                }
+                RelationNumaNode => {
+                    let node = unsafe { info.u.NumaNode() };
+                    numa_nodes.push(NumaNode {
+                        node_id: node.NodeNumber,
+                        logical_cores: mask_indices(info.ProcessorMask),
+                    });
+                }
+                RelationCache => {
+                    let descriptor = unsafe { info.u.Cache() };
+
+                    let size = descriptor.Size;
+                    let line_size = descriptor.LineSize as u32;
+                    let associativity = match descriptor.Associativity {
+                        CACHE_FULLY_ASSOCIATIVE if line_size > 0 => size / line_size,
+                        ways => ways as u32,
+                    };
+                    let properties = PhysicalDeviceCacheProperties::from_geometry(
+                        size,
+                        line_size,
+                        associativity,
+                    );
+                    let kind = match descriptor.Type {
+                        CacheData => Some(CacheKind::Data),
+                        CacheInstruction => Some(CacheKind::Instruction),
+                        CacheUnified => Some(CacheKind::Unified),
+                        _ => None,
+                    };
+                    let kind = match kind {
+                        Some(kind) => kind,
+                        None => continue,
+                    };
+
+                    let logical_cores = mask_indices(info.ProcessorMask);
+                    let known = caches.iter().any(|cache| {
+                        cache.level == descriptor.Level
+                            && cache.kind == kind
+                            && cache.logical_cores == logical_cores
+                    });
+                    if !known {
+                        caches.push(CacheInstance {
+                            level: descriptor.Level,
+                            kind,
+                            properties: PhysicalDeviceCacheProperties {
+                                shared_by_cores: info.ProcessorMask.count_ones(),
+                                ..properties.clone()
+                            },
+                            logical_cores,
+                        });
+                    }
+
+                    if descriptor.Level == 3 {
+                        // Collect the distinct L3 instances, size is derived per package below.
+                        if !l3_instances
+                            .iter()
+                            .any(|&(ty, mask, _)| ty == kind && mask == info.ProcessorMask)
+                        {
+                            l3_instances.push((kind, info.ProcessorMask, properties.size));
+                        }
+                    }
+
+                    let index = aggregates
+                        .iter()
+                        .position(|&(level, ty, _)| level == descriptor.Level && ty == kind)
+                        .unwrap_or_else(|| {
+                            aggregates.push((descriptor.Level, kind, Default::default()));
+                            aggregates.len() - 1
+                        });
+                    let cache = &mut aggregates[index].2;
+
+                    // The L4 is reported once per package like the L3, but not split.
+                    if descriptor.Level == 4 {
+                        cache.size = cache.size.max(properties.size);
+                    } else {
+                        cache.size += properties.size;
+                    }
+                    cache.line_size = properties.line_size;
+                    cache.associativity = properties.associativity;
+                    cache.sets = properties.sets;
+                    cache.shared_by_cores = info.ProcessorMask.count_ones();
+                }
+                _ => {}
+            }
+        }
+
+        // L3 caches are specified per package, which may contain multiple L3 instances.
+        let package_l3_size = |kind: CacheKind, package: usize| -> u32 {
+            l3_instances
+                .iter()
+                .filter(|&&(ty, mask, _)| ty == kind && mask & package != 0)
+                .map(|&(_, _, size)| size)
+                .sum()
+        };
+
+        let mut slots: [PhysicalDeviceCacheProperties; 6] = Default::default();
+        for (level, kind, mut cache) in aggregates {
+            match level {
+                // Keep the aggregate sizes if no core records were reported.
+                1 | 2 => cache.size = cache.size.checked_div(physical_cores).unwrap_or(cache.size),
+                3 => {
+                    cache.size = if packages.is_empty() {
+                        package_l3_size(kind, !0)
+                    } else {
+                        packages
+                            .iter()
+                            .map(|&package| package_l3_size(kind, package))
+                            .max()
+                            .unwrap_or(0)
+                    }
+                }
+                _ => {}
+            }
+            insert_cache(&mut slots, kind, level as u32, cache);
+        }
+        let [l1_cache_data, l1_cache_instruction, l2_cache, l3_cache, l4_cache, l1_cache_unified] =
+            slots;
+
+        ProcessorInformation {
+            logical_cores,
+            physical_cores: physical_cores as _,
+            l1_cache_data,
+            l1_cache_instruction,
+            l1_cache_unified,
+            l2_cache,
+            l3_cache,
+            l4_cache,
+            numa_nodes,
+            packages: packages
+                .iter()
+                .map(|&package| Package {
+                    logical_cores: mask_indices(package),
+                    physical_cores: cores.iter().filter(|&&core| core & package != 0).count(),
+                })
+                .collect(),
+            cores: cores
+                .iter()
+                .map(|&core| CoreAffinity {
+                    logical_cores: mask_indices(core),
+                })
+                .collect(),
+            caches,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn core(mask: usize) -> SYSTEM_LOGICAL_PROCESSOR_INFORMATION {
+        let mut info: SYSTEM_LOGICAL_PROCESSOR_INFORMATION = unsafe { std::mem::zeroed() };
+        info.ProcessorMask = mask;
+        info.Relationship = RelationProcessorCore;
+        info
+    }
+
+    fn package(mask: usize) -> SYSTEM_LOGICAL_PROCESSOR_INFORMATION {
+        let mut info: SYSTEM_LOGICAL_PROCESSOR_INFORMATION = unsafe { std::mem::zeroed() };
+        info.ProcessorMask = mask;
+        info.Relationship = RelationProcessorPackage;
+        info
+    }
+
+    fn cache(mask: usize, level: u8, size: u32) -> SYSTEM_LOGICAL_PROCESSOR_INFORMATION {
+        typed_cache(mask, level, size, CacheUnified)
+    }
+
+    fn typed_cache(
+        mask: usize,
+        level: u8,
+        size: u32,
+        ty: PROCESSOR_CACHE_TYPE,
+    ) -> SYSTEM_LOGICAL_PROCESSOR_INFORMATION {
+        let mut info: SYSTEM_LOGICAL_PROCESSOR_INFORMATION = unsafe { std::mem::zeroed() };
+        info.ProcessorMask = mask;
+        info.Relationship = RelationCache;
+        unsafe {
+            *info.u.Cache_mut() = CACHE_DESCRIPTOR {
+                Level: level,
+                Associativity: 16,
+                LineSize: 64,
+                Size: size,
+                Type: ty,
+            };
+        }
+        info
+    }
+
+    #[test]
+    fn logical_cores_from_core_masks() {
+        // Two SMT cores, with a package record only covering the first core.
+        let infos = [core(0b0011), core(0b1100), package(0b0011)];
+        let info = ProcessorInformation::parse(&infos);
+
+        assert_eq!(info.physical_cores, 2);
+        assert_eq!(info.logical_cores, 4);
+        assert_eq!(
+            info.cores,
+            [
+                CoreAffinity {
+                    logical_cores: vec![0, 1]
+                },
+                CoreAffinity {
+                    logical_cores: vec![2, 3]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn empty() {
+        let info = ProcessorInformation::parse(&[]);
+
+        assert_eq!(info.physical_cores, 0);
+        assert_eq!(info.logical_cores, 0);
+        assert_eq!(info.l2_cache, PhysicalDeviceCacheProperties::default());
+    }
+
+    #[test]
+    fn l3_per_package() {
+        const MIB: u32 = 1024 * 1024;
+
+        // Two packages with two L3 instances each, one instance reported twice.
+        let infos = [
+            package(0x0F),
+            package(0xF0),
+            cache(0x03, 3, 16 * MIB),
+            cache(0x0C, 3, 16 * MIB),
+            cache(0x0C, 3, 16 * MIB),
+            cache(0x30, 3, 16 * MIB),
+            cache(0xC0, 3, 16 * MIB),
+            cache(0x0F, 4, 128 * MIB),
+            cache(0xF0, 4, 128 * MIB),
+        ];
+        let info = ProcessorInformation::parse(&infos);
+
+        assert_eq!(info.l3_cache.size, 32 * MIB);
+        assert_eq!(info.l3_cache.line_size, 64);
+        assert_eq!(info.l3_cache.shared_by_cores, 2);
+        assert_eq!(info.l4_cache.size, 128 * MIB);
+    }
+
+    #[test]
+    fn typed_l2() {
+        const KIB: u32 = 1024;
+
+        // Split L2 per core, the larger data cache is kept.
+        let infos = [
+            core(0b01),
+            core(0b10),
+            typed_cache(0b01, 2, 1024 * KIB, CacheData),
+            typed_cache(0b01, 2, 512 * KIB, CacheInstruction),
+            typed_cache(0b10, 2, 1024 * KIB, CacheData),
+            typed_cache(0b10, 2, 512 * KIB, CacheInstruction),
+        ];
+        let info = ProcessorInformation::parse(&infos);
+
+        assert_eq!(info.l2_cache.size, 1024 * KIB);
+        assert_eq!(info.l2_cache.shared_by_cores, 1);
+    }
+
+    #[test]
+    fn packages() {
+        let infos = [
+            core(0b0011),
+            core(0b1100),
+            core(0b11_0000),
+            package(0b1111),
+            package(0b11_0000),
+        ];
+        let info = ProcessorInformation::parse(&infos);
+
+        assert_eq!(
+            info.packages,
+            [
+                Package {
+                    logical_cores: vec![0, 1, 2, 3],
+                    physical_cores: 2,
+                },
+                Package {
+                    logical_cores: vec![4, 5],
+                    physical_cores: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn numa_nodes() {
+        let mut node = unsafe { std::mem::zeroed::<SYSTEM_LOGICAL_PROCESSOR_INFORMATION>() };
+        node.ProcessorMask = 0b1010;
+        node.Relationship = RelationNumaNode;
+        unsafe { node.u.NumaNode_mut().NodeNumber = 1 };
+
+        let info = ProcessorInformation::parse(&[core(0b0011), core(0b1100), node]);
+        assert_eq!(
+            info.numa_nodes,
+            [NumaNode {
+                node_id: 1,
+                logical_cores: vec![1, 3],
+            }]
+        );
+    }
+}
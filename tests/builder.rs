@@ -0,0 +1,80 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use norse_physical_device::{
+    PhysicalDevice, PhysicalDeviceCacheProperties, PhysicalDeviceProperties, Vendor,
+};
+
+#[test]
+fn builder() {
+    let expected = common::ryzen_5950x();
+    let properties = PhysicalDeviceProperties::builder()
+        .vendor(Vendor::AMD)
+        .device("AMD Ryzen 9 5950X 16-Core Processor")
+        .family(0x19)
+        .model(0x21)
+        .microarchitecture(Some("Zen 3".into()))
+        .logical_cores(32)
+        .physical_cores(16)
+        .threads_per_core(2)
+        .package_count(1)
+        .l1_cache_data(expected.l1_cache_data.clone())
+        .l1_cache_instruction(expected.l1_cache_instruction.clone())
+        .l1_cache_unified(None)
+        .l2_cache(expected.l2_cache.clone())
+        .l3_cache(expected.l3_cache.clone())
+        .l4_cache(expected.l4_cache.clone())
+        .recommended_prefetch_bytes(128)
+        .l1_tlb_data(expected.l1_tlb_data.clone())
+        .l1_tlb_instruction(expected.l1_tlb_instruction.clone())
+        .l2_tlb(expected.l2_tlb.clone())
+        .base_frequency_mhz(3400)
+        .max_frequency_mhz(4900)
+        .current_frequency_mhz(3600)
+        .turbo_enabled(Some(true))
+        .invariant_tsc(true)
+        .tdp_watts(Some(105))
+        .total_memory_bytes(expected.total_memory_bytes)
+        .available_memory_bytes(expected.available_memory_bytes)
+        .large_page_sizes(expected.large_page_sizes.clone())
+        .core_clusters(expected.core_clusters.clone())
+        .core_complexes(expected.core_complexes.clone())
+        .numa_nodes(expected.numa_nodes.clone())
+        .translation(None)
+        .security_features(expected.security_features.clone())
+        .pmu_counters(expected.pmu_counters.clone())
+        .build();
+
+    assert_eq!(properties, expected);
+}
+
+#[test]
+fn from_properties() {
+    let properties = PhysicalDeviceProperties::builder()
+        .vendor(Vendor::AMD)
+        .physical_cores(64)
+        .logical_cores(128)
+        .l3_cache(PhysicalDeviceCacheProperties {
+            size: 256 * 1024 * 1024,
+            ..Default::default()
+        })
+        .build();
+    let device = PhysicalDevice::from_properties(properties.clone());
+
+    assert_eq!(device.properties(), &properties);
+    assert!(!device.features().avx2);
+    assert_eq!(device.properties().device, "");
+}
+
+#[test]
+fn properties_mut() {
+    let mut device = PhysicalDevice::from_properties(common::ryzen_5950x());
+    device.properties_mut().l3_cache = PhysicalDeviceCacheProperties::default();
+
+    assert!(!device.properties().l3_cache.is_present());
+    assert_eq!(
+        device.summary_line(),
+        "AMD Ryzen 9 5950X 16-Core Processor (16c/32t)"
+    );
+}
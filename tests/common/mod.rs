@@ -0,0 +1,106 @@
+#![allow(dead_code)]
+
+use norse_physical_device::{
+    CacheInclusivity, CacheWritePolicy, CoreCluster, CoreComplex, CoreType, Endianness, NumaNode,
+    PhysicalDeviceCacheProperties, PhysicalDeviceProperties, PhysicalDeviceTlbProperties, PmuInfo,
+    SecurityFeatures, Vendor,
+};
+
+/// Properties of an AMD Ryzen 9 5950X.
+pub fn ryzen_5950x() -> PhysicalDeviceProperties {
+    PhysicalDeviceProperties {
+        vendor: Vendor::AMD,
+        device: "AMD Ryzen 9 5950X 16-Core Processor".into(),
+        family: 0x19,
+        model: 0x21,
+        stepping: 0,
+        microarchitecture: Some("Zen 3".into()),
+        logical_cores: 32,
+        physical_cores: 16,
+        threads_per_core: 2,
+        package_count: 1,
+        l1_cache_data: PhysicalDeviceCacheProperties {
+            size: 32 * 1024,
+            line_size: 64,
+            associativity: 8,
+            sets: 64,
+            inclusivity: CacheInclusivity::Unknown,
+            write_policy: CacheWritePolicy::WriteBack,
+            shared_by_cores: 2,
+        },
+        l1_cache_instruction: PhysicalDeviceCacheProperties {
+            size: 32 * 1024,
+            line_size: 64,
+            associativity: 8,
+            sets: 64,
+            inclusivity: CacheInclusivity::Unknown,
+            write_policy: CacheWritePolicy::Unknown,
+            shared_by_cores: 2,
+        },
+        l1_cache_unified: None,
+        l2_cache: PhysicalDeviceCacheProperties {
+            size: 512 * 1024,
+            line_size: 64,
+            associativity: 8,
+            sets: 1024,
+            inclusivity: CacheInclusivity::Inclusive,
+            write_policy: CacheWritePolicy::WriteBack,
+            shared_by_cores: 2,
+        },
+        l3_cache: PhysicalDeviceCacheProperties {
+            size: 64 * 1024 * 1024,
+            line_size: 64,
+            associativity: 16,
+            sets: 65536,
+            inclusivity: CacheInclusivity::Exclusive,
+            write_policy: CacheWritePolicy::WriteBack,
+            shared_by_cores: 16,
+        },
+        l4_cache: Default::default(),
+        recommended_prefetch_bytes: 128,
+        l1_tlb_data: PhysicalDeviceTlbProperties {
+            entries: 64,
+            page_size_bytes: 4096,
+        },
+        l1_tlb_instruction: PhysicalDeviceTlbProperties {
+            entries: 64,
+            page_size_bytes: 4096,
+        },
+        l2_tlb: PhysicalDeviceTlbProperties {
+            entries: 2048,
+            page_size_bytes: 4096,
+        },
+        base_frequency_mhz: 3400,
+        max_frequency_mhz: 4900,
+        current_frequency_mhz: 3600,
+        turbo_enabled: Some(true),
+        invariant_tsc: true,
+        tdp_watts: Some(105),
+        total_memory_bytes: 64 << 30,
+        available_memory_bytes: 48 << 30,
+        large_page_sizes: vec![2 << 20, 1 << 30],
+        core_clusters: vec![CoreCluster {
+            core_type: CoreType::Unknown,
+            logical_cores: (0..32).collect(),
+        }],
+        core_complexes: (0..2)
+            .map(|ccx| CoreComplex {
+                logical_cores: (ccx * 16..ccx * 16 + 16).collect(),
+                l3_cache_bytes: 32 * 1024 * 1024,
+            })
+            .collect(),
+        numa_nodes: vec![NumaNode {
+            node_id: 0,
+            logical_cores: (0..32).collect(),
+        }],
+        hypervisor: None,
+        translation: None,
+        security_features: SecurityFeatures {
+            tme: true,
+            ..Default::default()
+        },
+        pmu_counters: PmuInfo::default(),
+        endianness: Endianness::Little,
+        pointer_width_bits: 64,
+    }
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use norse_physical_device::Vendor;
+
+#[test]
+fn vendor() {
+    assert_eq!(Vendor::Intel.to_string(), "Intel");
+    assert_eq!(Vendor::AMD.to_string(), "AMD");
+    assert_eq!(Vendor::Unknown.to_string(), "Unknown");
+}
+
+#[test]
+fn properties() {
+    let mut properties = common::ryzen_5950x();
+    properties.l2_cache.size = 1280 * 1024;
+
+    assert_eq!(
+        properties.to_string(),
+        "AMD Ryzen 9 5950X 16-Core Processor\n\
+         16 cores (32 threads)\n\
+         L1d 32 KiB, L1i 32 KiB, L2 1280 KiB, L3 64 MiB"
+    );
+}
+
+#[test]
+fn report() {
+    let mut properties = common::ryzen_5950x();
+    properties.numa_nodes[0].logical_cores = vec![0, 1, 2, 3, 8, 10, 11];
+
+    let report = properties.to_report_string();
+    assert!(report.starts_with(
+        "[device]\n\
+         vendor: AMD\n\
+         device: AMD Ryzen 9 5950X 16-Core Processor\n\
+         family: 0x19\n\
+         model: 0x21\n\
+         stepping: 0\n\
+         microarchitecture: Zen 3\n\
+         hypervisor: none\n\
+         translation: none\n\
+         endianness: little\n\
+         pointer_width_bits: 64\n\
+         \n\
+         [cores]\n\
+         logical: 32\n\
+         physical: 16\n\
+         threads_per_core: 2\n\
+         packages: 1\n"
+    ));
+    assert!(report.contains(
+        "[cache.l3]\n\
+         size_bytes: 67108864\n\
+         line_size: 64\n\
+         associativity: 16\n\
+         sets: 65536\n\
+         inclusivity: exclusive\n\
+         write_policy: write-back\n\
+         shared_by_cores: 16\n"
+    ));
+    assert!(report.contains(
+        "large_page_sizes_bytes: 2097152 1073741824\n\
+         recommended_prefetch_bytes: 128\n"
+    ));
+    assert!(
+        report.contains("current_mhz: 3600\nturbo: enabled\ninvariant_tsc: true\ntdp_watts: 105\n")
+    );
+    assert!(report.contains(
+        "[security]\n\
+         sgx: false\n\
+         sev: false\n\
+         tme: true\n\
+         \n\
+         [pmu]\n\
+         version: 0\n\
+         general_purpose_counters: 0 (0 bit)\n\
+         fixed_counters: 0 (0 bit)\n"
+    ));
+    assert!(report.ends_with(
+        "[numa_node.0]\n\
+         logical_cores: 0-3,8,10-11\n"
+    ));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn summary_line() {
+    use norse_physical_device::PhysicalDevice;
+
+    let mut properties = common::ryzen_5950x();
+    let device = PhysicalDevice::from_properties(properties.clone());
+    assert_eq!(
+        device.summary_line(),
+        "AMD Ryzen 9 5950X 16-Core Processor (16c/32t, L3 64 MiB)"
+    );
+
+    properties.device = "Very Long Brand String Which Exceeds The Limit Of CPUID".into();
+    properties.l3_cache.size = 0;
+    let device = PhysicalDevice::from_properties(properties.clone());
+    assert_eq!(
+        device.summary_line(),
+        "Very Long Brand String Which Exceeds The Limit… (16c/32t)"
+    );
+
+    properties.device = String::new();
+    let device = PhysicalDevice::from_properties(properties);
+    assert_eq!(device.summary_line(), "AMD (16c/32t)");
+}
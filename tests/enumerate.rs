@@ -0,0 +1,594 @@
+#![cfg(feature = "std")]
+
+use norse_physical_device::{DetectionBackend, Endianness, PhysicalDevice, Vendor};
+
+#[test]
+fn enumerate() {
+    let device = PhysicalDevice::enumerate();
+    let properties = device.properties();
+
+    assert!(properties.logical_cores > 0);
+    assert!(properties.physical_cores > 0);
+    assert!(properties.logical_cores >= properties.physical_cores);
+}
+
+#[test]
+fn try_enumerate() {
+    let device = PhysicalDevice::try_enumerate().unwrap();
+    assert!(device.properties().logical_cores > 0);
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn enumerate_async() {
+    use std::future::Future;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake};
+    use std::thread::{self, Thread};
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    // Minimal executor, parking the thread until the future is woken.
+    let mut future = Box::pin(PhysicalDevice::try_enumerate_async());
+    let waker = Arc::new(ThreadWaker(thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    let device = loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(device) => break device.unwrap(),
+            Poll::Pending => thread::park(),
+        }
+    };
+
+    assert!(device.is_same_hardware(&PhysicalDevice::enumerate()));
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[test]
+fn features() {
+    let device = PhysicalDevice::enumerate();
+    let features = device.features();
+
+    // SSE2 is part of the x86_64 baseline.
+    assert!(features.sse && features.sse2);
+    assert_eq!(features.fxsr, is_x86_feature_detected!("fxsr"));
+    #[cfg(target_arch = "x86_64")]
+    assert!(features.x86_64_level() >= 1);
+    assert_eq!(features.avx, is_x86_feature_detected!("avx"));
+    assert_eq!(features.avx2, is_x86_feature_detected!("avx2"));
+    assert_eq!(features.avx512f, is_x86_feature_detected!("avx512f"));
+    assert_eq!(features.avx512vl, is_x86_feature_detected!("avx512vl"));
+    assert_eq!(features.avx512bw, is_x86_feature_detected!("avx512bw"));
+    assert_eq!(features.avx512dq, is_x86_feature_detected!("avx512dq"));
+    assert_eq!(features.avx512cd, is_x86_feature_detected!("avx512cd"));
+    assert_eq!(features.avx512vnni, is_x86_feature_detected!("avx512vnni"));
+    assert_eq!(features.avx512_bf16, is_x86_feature_detected!("avx512bf16"));
+    assert_eq!(features.fma, is_x86_feature_detected!("fma"));
+    assert_eq!(features.f16c, is_x86_feature_detected!("f16c"));
+    assert_eq!(features.bmi1, is_x86_feature_detected!("bmi1"));
+    assert_eq!(features.lzcnt, is_x86_feature_detected!("lzcnt"));
+    assert_eq!(features.popcnt, is_x86_feature_detected!("popcnt"));
+    assert_eq!(features.cmpxchg16b, is_x86_feature_detected!("cmpxchg16b"));
+    assert_eq!(features.movbe, is_x86_feature_detected!("movbe"));
+    assert_eq!(features.rdrand, is_x86_feature_detected!("rdrand"));
+    assert_eq!(features.rdseed, is_x86_feature_detected!("rdseed"));
+    assert_eq!(features.aes, is_x86_feature_detected!("aes"));
+    assert_eq!(features.pclmulqdq, is_x86_feature_detected!("pclmulqdq"));
+    assert_eq!(features.sha, is_x86_feature_detected!("sha"));
+
+    // CLFLUSH is implied by SSE2.
+    assert!(features.clflush);
+    assert!(features.clflush_line_size.is_power_of_two());
+
+    assert!(features.monitor_line_size_min <= features.monitor_line_size_max);
+    if !features.monitor {
+        assert_eq!(features.monitor_line_size_max, 0);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+#[test]
+fn features() {
+    use std::arch::is_aarch64_feature_detected;
+
+    let device = PhysicalDevice::enumerate();
+    let features = device.features();
+
+    assert_eq!(features.neon, is_aarch64_feature_detected!("neon"));
+    assert_eq!(features.sve, is_aarch64_feature_detected!("sve"));
+    assert_eq!(features.sve2, is_aarch64_feature_detected!("sve2"));
+    assert_eq!(features.dotprod, is_aarch64_feature_detected!("dotprod"));
+    if !features.sve {
+        assert_eq!(features.sve_vector_bits, 0);
+    }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[test]
+fn frequency() {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::__cpuid;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::__cpuid;
+
+    let device = PhysicalDevice::enumerate();
+    let properties = device.properties();
+
+    let vendor = __cpuid(0);
+    let intel = (vendor.ebx, vendor.edx, vendor.ecx) == (0x756e_6547, 0x4965_6e69, 0x6c65_746e);
+    if intel && vendor.eax >= 0x16 && __cpuid(0x16).eax != 0 {
+        assert_ne!(properties.base_frequency_mhz, 0);
+        assert_ne!(properties.max_frequency_mhz, 0);
+    }
+}
+
+#[test]
+fn numa_nodes() {
+    let device = PhysicalDevice::enumerate();
+    let properties = device.properties();
+
+    assert!(!properties.numa_nodes.is_empty());
+    let cores = properties
+        .numa_nodes
+        .iter()
+        .map(|node| node.logical_cores.len())
+        .sum::<usize>();
+    assert!(cores > 0);
+}
+
+#[test]
+fn cached() {
+    let device = PhysicalDevice::cached();
+    assert!(std::ptr::eq(device, PhysicalDevice::cached()));
+    assert!(device.properties().logical_cores > 0);
+}
+
+#[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+#[test]
+fn memory() {
+    let device = PhysicalDevice::enumerate();
+    let properties = device.properties();
+
+    assert!(properties.total_memory_bytes > 0);
+    assert!(properties.available_memory_bytes <= properties.total_memory_bytes);
+}
+
+#[test]
+fn threads_per_core() {
+    let device = PhysicalDevice::enumerate();
+    let properties = device.properties();
+
+    assert!(properties.threads_per_core >= 1);
+    assert_eq!(
+        properties.is_hyperthreaded(),
+        properties.threads_per_core > 1
+    );
+    if properties.logical_cores == properties.physical_cores {
+        assert_eq!(properties.threads_per_core, 1);
+    }
+}
+
+#[test]
+fn core_clusters() {
+    let device = PhysicalDevice::enumerate();
+    let properties = device.properties();
+
+    assert!(!properties.core_clusters.is_empty());
+    assert!(properties
+        .core_clusters
+        .iter()
+        .all(|cluster| !cluster.logical_cores.is_empty()));
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[test]
+fn device_name() {
+    let device = PhysicalDevice::enumerate();
+    let properties = device.properties();
+
+    if properties.vendor != Vendor::Unknown {
+        assert!(!properties.device.is_empty());
+        assert_eq!(properties.device, properties.device.trim());
+    }
+}
+
+#[test]
+fn enumerate_all() {
+    let devices = PhysicalDevice::enumerate_all();
+    assert!(!devices.is_empty());
+
+    let logical_cores = devices
+        .iter()
+        .map(|device| device.properties().logical_cores)
+        .sum::<usize>();
+    if devices.len() == 1 {
+        assert_eq!(
+            logical_cores,
+            PhysicalDevice::enumerate().properties().logical_cores
+        );
+    }
+
+    // The device restricted to the allowed cores still counts all packages.
+    assert_eq!(
+        PhysicalDevice::enumerate().properties().package_count,
+        devices.len()
+    );
+    assert!(devices
+        .iter()
+        .all(|device| device.properties().package_count == 1));
+
+    // Each package reports its own vendor, which should never be unknown
+    // if the machine is detected.
+    let vendor = PhysicalDevice::enumerate().properties().vendor;
+    if vendor != Vendor::Unknown {
+        assert!(devices
+            .iter()
+            .all(|device| device.properties().vendor != Vendor::Unknown));
+    }
+    assert!(devices
+        .iter()
+        .all(|device| device.properties().physical_cores > 0));
+}
+
+#[test]
+fn tlbs() {
+    let device = PhysicalDevice::enumerate();
+    let properties = device.properties();
+
+    for tlb in [
+        &properties.l1_tlb_data,
+        &properties.l1_tlb_instruction,
+        &properties.l2_tlb,
+    ] {
+        assert_eq!(tlb.entries == 0, tlb.page_size_bytes == 0);
+    }
+}
+
+#[test]
+fn hypervisor() {
+    let device = PhysicalDevice::enumerate();
+
+    // The hypervisor doesn't change while running.
+    assert_eq!(
+        device.properties().hypervisor,
+        PhysicalDevice::cached().properties().hypervisor
+    );
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn cpuid_faulting() {
+    use norse_physical_device::{cpuid_available, DeviceError};
+
+    const ARCH_SET_CPUID: libc::c_int = 0x1012;
+    const CHILD: &str = "NORSE_TEST_CPUID_FAULTING";
+
+    // CPUID faulting can't be disabled again, run the test in a separate process.
+    if std::env::var_os(CHILD).is_none() {
+        let status = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["cpuid_faulting", "--exact", "--test-threads=1"])
+            .env(CHILD, "1")
+            .status()
+            .unwrap();
+        assert!(status.success());
+        return;
+    }
+
+    if unsafe { libc::syscall(libc::SYS_arch_prctl, ARCH_SET_CPUID, 0) } != 0 {
+        // Not supported by the CPU or kernel.
+        return;
+    }
+    assert!(!cpuid_available());
+
+    let device = PhysicalDevice::enumerate();
+    assert_eq!(device.properties().vendor, Vendor::Unknown);
+    assert!(device.properties().logical_cores > 0);
+    assert!(device.features().sse2);
+    assert!(device.properties().apic_ids().is_empty());
+    assert!(device.cpuid(0, 0).is_none());
+    assert!(matches!(
+        PhysicalDevice::try_enumerate_with(DetectionBackend::Cpuid),
+        Err(DeviceError::UnsupportedBackend)
+    ));
+}
+
+#[test]
+fn pmu_counters() {
+    let device = PhysicalDevice::enumerate();
+    let pmu = &device.properties().pmu_counters;
+
+    if pmu.version == 0 {
+        assert_eq!(pmu.general_purpose_counters, 0);
+    }
+    if pmu.version < 2 {
+        assert_eq!(pmu.fixed_counters, 0);
+    }
+}
+
+#[test]
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn from_cpuid() {
+    use norse_physical_device::PhysicalDeviceProperties;
+
+    let cpuid = PhysicalDeviceProperties::from_cpuid();
+    let system = PhysicalDeviceProperties::system();
+
+    assert_eq!(cpuid.vendor, system.vendor);
+    assert_eq!(cpuid.device, system.device);
+    assert_eq!(cpuid.family, system.family);
+    assert_eq!(cpuid.logical_cores, 0);
+    assert!(cpuid.threads_per_core >= 1);
+    assert!(cpuid.numa_nodes.is_empty());
+}
+
+#[test]
+fn core_affinity() {
+    let device = PhysicalDevice::enumerate();
+    let properties = device.properties();
+    let cores = properties.core_affinity();
+
+    // Each logical core belongs to exactly one physical core.
+    let mut logical_cores = cores
+        .iter()
+        .flat_map(|core| core.logical_cores.iter().copied())
+        .collect::<Vec<_>>();
+    let count = logical_cores.len();
+    logical_cores.sort_unstable();
+    logical_cores.dedup();
+    assert_eq!(logical_cores.len(), count);
+}
+
+#[test]
+fn large_page_sizes() {
+    let device = PhysicalDevice::enumerate();
+    let sizes = &device.properties().large_page_sizes;
+
+    assert!(sizes.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(sizes
+        .iter()
+        .all(|size| *size > 4096 && size.is_power_of_two()));
+}
+
+#[test]
+fn data_layout() {
+    let device = PhysicalDevice::enumerate();
+    let properties = device.properties();
+
+    assert_eq!(properties.endianness, Endianness::native());
+    assert_eq!(properties.pointer_width_bits as u32, usize::BITS);
+}
+
+#[test]
+fn refresh() {
+    let mut device = PhysicalDevice::enumerate();
+    let before = device.properties().clone();
+    device.refresh();
+    let after = device.properties();
+
+    assert_eq!(after.device, before.device);
+    assert_eq!(after.numa_nodes, before.numa_nodes);
+    assert_eq!(after.total_memory_bytes, before.total_memory_bytes);
+    assert!(after.available_memory_bytes <= after.total_memory_bytes);
+}
+
+#[test]
+fn total_cores() {
+    let devices = PhysicalDevice::enumerate_all();
+    let properties = devices[0].properties();
+
+    assert!(properties.total_logical_cores() >= properties.total_physical_cores());
+    for device in &devices {
+        assert!(device.properties().logical_cores <= properties.total_logical_cores());
+        assert!(device.properties().physical_cores <= properties.total_physical_cores());
+    }
+}
+
+#[test]
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn cpuid() {
+    let device = PhysicalDevice::enumerate();
+
+    let leaf0 = device.cpuid(0, 0).unwrap();
+    assert!(device.cpuid(leaf0.eax, 0).is_some());
+    assert!(device.cpuid(leaf0.eax + 1, 0).is_none());
+
+    let extended = device.cpuid(0x80000000, 0).unwrap();
+    assert!(device.cpuid(extended.eax + 1, 0).is_none());
+}
+
+#[test]
+fn topology() {
+    let device = PhysicalDevice::enumerate();
+    let topology = device.topology();
+
+    let mut logical_cores = topology
+        .cores()
+        .flat_map(|core| core.logical_cores.iter().copied())
+        .collect::<Vec<_>>();
+    logical_cores.sort_unstable();
+    let mut expected = device
+        .properties()
+        .numa_nodes
+        .iter()
+        .flat_map(|node| node.logical_cores.iter().copied())
+        .collect::<Vec<_>>();
+    expected.sort_unstable();
+    assert_eq!(logical_cores, expected);
+}
+
+#[test]
+fn recommended_prefetch_bytes() {
+    let device = PhysicalDevice::enumerate();
+    let properties = device.properties();
+
+    assert_eq!(properties.recommended_prefetch_bytes % 2, 0);
+    if properties.l2_cache.line_size > 0 {
+        assert_eq!(
+            properties.recommended_prefetch_bytes,
+            2 * properties.l2_cache.line_size
+        );
+    }
+}
+
+#[test]
+fn system_constrained() {
+    use norse_physical_device::PhysicalDeviceProperties;
+
+    let system = PhysicalDeviceProperties::system();
+    let constrained = PhysicalDeviceProperties::system_constrained();
+
+    assert!(constrained.logical_cores >= 1);
+    assert!(constrained.logical_cores <= system.total_logical_cores());
+    assert!(constrained.physical_cores <= constrained.logical_cores);
+}
+
+#[cfg(all(target_os = "linux", any(target_arch = "x86", target_arch = "x86_64")))]
+#[test]
+fn invariant_tsc() {
+    let device = PhysicalDevice::enumerate();
+    let cpuinfo = std::fs::read_to_string("/proc/cpuinfo").unwrap();
+    let flags = cpuinfo
+        .lines()
+        .find(|line| line.starts_with("flags"))
+        .unwrap();
+    let has = |flag: &str| flags.split_whitespace().any(|f| f == flag);
+
+    if has("constant_tsc") && has("nonstop_tsc") {
+        assert!(device.properties().invariant_tsc);
+    }
+}
+
+#[test]
+fn smt_siblings() {
+    let device = PhysicalDevice::enumerate();
+    let siblings = device.properties().smt_siblings();
+
+    for (index, (core, logical_cores)) in siblings.iter().enumerate() {
+        assert_eq!(*core, index);
+        assert!(!logical_cores.is_empty());
+    }
+    assert!(siblings
+        .windows(2)
+        .all(|pair| pair[0].1.first() < pair[1].1.first()));
+}
+
+#[test]
+fn debug_snapshot() {
+    let device = PhysicalDevice::enumerate();
+    let snapshot = device.debug_snapshot();
+
+    assert!(snapshot.starts_with("[snapshot]\nformat: 1\n"));
+    assert!(snapshot.contains(&device.properties().to_report_string()));
+    assert!(snapshot.contains("\n[features]\nsse: "));
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        assert!(snapshot.contains("\n[cpuid]\n0x00000000.0: "));
+        assert!(snapshot.contains("\n0x00000001.0: "));
+        for leaf in [0xA, 0x12, 0x18, 0x8000001F] {
+            if device.cpuid(leaf, 0).is_some() {
+                assert!(snapshot.contains(&format!("\n{:#010x}.0: ", leaf)));
+            }
+        }
+    }
+}
+
+#[test]
+fn is_same_hardware() {
+    let mut properties = PhysicalDevice::enumerate().properties().clone();
+    let device = PhysicalDevice::from_properties(properties.clone());
+    assert!(device.is_same_hardware(&device));
+
+    properties.available_memory_bytes /= 2;
+    properties.current_frequency_mhz += 100;
+    assert!(device.is_same_hardware(&PhysicalDevice::from_properties(properties.clone())));
+
+    properties.physical_cores += 1;
+    assert!(!device.is_same_hardware(&PhysicalDevice::from_properties(properties)));
+}
+
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+#[test]
+fn affinity_supported() {
+    let device = PhysicalDevice::enumerate();
+    assert!(device.affinity_supported());
+
+    // Probing leaves the affinity mask untouched.
+    let after = PhysicalDevice::enumerate();
+    assert_eq!(
+        after.properties().logical_cores,
+        device.properties().logical_cores
+    );
+}
+
+#[test]
+fn apic_ids() {
+    let device = PhysicalDevice::enumerate();
+    let mut ids = device.properties().apic_ids();
+
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        any(target_os = "linux", target_os = "windows")
+    ))]
+    assert!(!ids.is_empty());
+    assert!(ids.len() <= device.properties().logical_cores);
+    let count = ids.len();
+    ids.sort_unstable();
+    ids.dedup();
+    assert_eq!(ids.len(), count);
+}
+
+#[cfg(all(feature = "hwloc", target_os = "linux"))]
+#[test]
+fn hwloc_backend() {
+    let hwloc = match PhysicalDevice::try_enumerate_with(DetectionBackend::Hwloc) {
+        Ok(device) => device,
+        Err(err) => {
+            eprintln!("skipping, hwloc unavailable: {}", err);
+            return;
+        }
+    };
+    let sysfs = PhysicalDevice::enumerate_with(DetectionBackend::Sysfs);
+    let (hwloc, sysfs) = (hwloc.properties(), sysfs.properties());
+
+    assert_eq!(hwloc.logical_cores, sysfs.logical_cores);
+    assert_eq!(hwloc.physical_cores, sysfs.physical_cores);
+    for (hwloc, sysfs) in [
+        (&hwloc.l1_cache_data, &sysfs.l1_cache_data),
+        (&hwloc.l2_cache, &sysfs.l2_cache),
+        (&hwloc.l3_cache, &sysfs.l3_cache),
+    ] {
+        assert_eq!(hwloc.size, sysfs.size);
+        assert_eq!(hwloc.line_size, sysfs.line_size);
+    }
+}
+
+#[test]
+fn enumerate_with() {
+    let device = PhysicalDevice::enumerate_with(DetectionBackend::Auto);
+    assert!(device.is_same_hardware(&PhysicalDevice::enumerate()));
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        let device = PhysicalDevice::enumerate_with(DetectionBackend::Cpuid);
+        assert!(device.properties().logical_cores > 0);
+        assert_eq!(
+            device.properties().vendor,
+            PhysicalDevice::enumerate().properties().vendor
+        );
+    }
+    #[cfg(all(
+        target_os = "linux",
+        any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")
+    ))]
+    assert!(PhysicalDevice::try_enumerate_with(DetectionBackend::Sysfs).is_ok());
+    #[cfg(not(feature = "hwloc"))]
+    assert_eq!(
+        PhysicalDevice::try_enumerate_with(DetectionBackend::Hwloc).err(),
+        Some(norse_physical_device::DeviceError::UnsupportedBackend)
+    );
+}
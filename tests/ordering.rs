@@ -0,0 +1,34 @@
+mod common;
+
+use norse_physical_device::Vendor;
+use std::collections::{BTreeSet, HashSet};
+
+#[test]
+fn dedup() {
+    let properties = common::ryzen_5950x();
+    let mut other = properties.clone();
+    other.available_memory_bytes -= 1 << 30;
+
+    let set: HashSet<_> = vec![properties.clone(), properties.clone(), other.clone()]
+        .into_iter()
+        .collect();
+    assert_eq!(set.len(), 2);
+
+    let set: BTreeSet<_> = vec![other, properties.clone(), properties]
+        .into_iter()
+        .collect();
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn natural_key() {
+    let amd = common::ryzen_5950x();
+    let mut intel = amd.clone();
+    intel.vendor = Vendor::Intel;
+    let mut smaller = amd.clone();
+    smaller.physical_cores = 8;
+    smaller.total_memory_bytes *= 2;
+
+    assert!(intel < amd);
+    assert!(smaller < amd);
+}
@@ -0,0 +1,44 @@
+#![cfg(feature = "env-override")]
+
+use norse_physical_device::{DeviceError, PhysicalDevice};
+use std::env;
+
+// Single test to avoid races between tests modifying the environment.
+#[test]
+fn overrides() {
+    let detected = PhysicalDevice::try_enumerate().unwrap();
+
+    env::set_var("NORSE_PHYSICAL_CORES", "128");
+    env::set_var("NORSE_DEVICE", "Simulated CPU");
+    let device = PhysicalDevice::try_enumerate().unwrap();
+    assert_eq!(device.properties().physical_cores, 128);
+    assert_eq!(device.properties().device, "Simulated CPU");
+    assert_eq!(device.properties().vendor, detected.properties().vendor);
+
+    let path = env::temp_dir().join("norse-device-override.json");
+    std::fs::write(&path, r#"{ "logical_cores": 256, "physical_cores": 64 }"#).unwrap();
+    env::set_var("NORSE_DEVICE_OVERRIDE", &path);
+    let device = PhysicalDevice::try_enumerate().unwrap();
+    // Single variables take precedence over the file.
+    assert_eq!(device.properties().logical_cores, 256);
+    assert_eq!(device.properties().physical_cores, 128);
+
+    env::set_var("NORSE_LOGICAL_CORES", "many");
+    assert!(matches!(
+        PhysicalDevice::try_enumerate(),
+        Err(DeviceError::InvalidOverride(_))
+    ));
+
+    for var in [
+        "NORSE_PHYSICAL_CORES",
+        "NORSE_LOGICAL_CORES",
+        "NORSE_DEVICE",
+        "NORSE_DEVICE_OVERRIDE",
+    ] {
+        env::remove_var(var);
+    }
+    std::fs::remove_file(&path).unwrap();
+    assert!(PhysicalDevice::try_enumerate()
+        .unwrap()
+        .is_same_hardware(&detected));
+}
@@ -0,0 +1,61 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use norse_physical_device::{
+    FieldDiff, PhysicalDevice, PhysicalDeviceFeatures, PhysicalDeviceProfile, Vendor,
+};
+
+#[test]
+fn matching() {
+    let device = PhysicalDevice::from_properties(common::ryzen_5950x());
+    let profile = PhysicalDeviceProfile {
+        name: "ryzen".into(),
+        vendor: Some(Vendor::AMD),
+        physical_cores: Some(16),
+        l3_cache_bytes: Some(64 << 20),
+        ..Default::default()
+    };
+
+    assert!(device.diff(&PhysicalDeviceProfile::default()).is_empty());
+    assert!(device.diff(&profile).is_empty());
+}
+
+#[test]
+fn diff() {
+    let device = PhysicalDevice::from_properties(common::ryzen_5950x());
+    let profile = PhysicalDeviceProfile {
+        name: "runner".into(),
+        vendor: Some(Vendor::Intel),
+        logical_cores: Some(32),
+        l2_cache_bytes: Some(1 << 20),
+        features: Some(PhysicalDeviceFeatures {
+            avx2: true,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let diffs = device.diff(&profile);
+    assert_eq!(
+        diffs,
+        [
+            FieldDiff {
+                field: "vendor".into(),
+                expected: "Intel".into(),
+                actual: "AMD".into(),
+            },
+            FieldDiff {
+                field: "l2_cache_bytes".into(),
+                expected: "1048576".into(),
+                actual: "524288".into(),
+            },
+            FieldDiff {
+                field: "features.avx2".into(),
+                expected: "true".into(),
+                actual: "false".into(),
+            },
+        ]
+    );
+    assert_eq!(diffs[0].to_string(), "vendor: expected Intel, found AMD");
+}
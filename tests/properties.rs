@@ -0,0 +1,436 @@
+mod common;
+
+use norse_physical_device::{
+    DeviceClass, EffectiveCachePerThread, Penalty, PhysicalDeviceFeatures, Vendor, Workload,
+};
+
+#[test]
+fn dominant_cache_line_size() {
+    let mut properties = common::ryzen_5950x();
+    assert_eq!(properties.dominant_cache_line_size(), 64);
+
+    properties.l1_cache_instruction.line_size = 0;
+    properties.l3_cache.line_size = 128;
+    properties.l2_cache.line_size = 128;
+    assert_eq!(properties.dominant_cache_line_size(), 128);
+
+    properties.l1_cache_data.line_size = 0;
+    properties.l2_cache.line_size = 0;
+    properties.l3_cache.line_size = 0;
+    assert_eq!(properties.dominant_cache_line_size(), 0);
+}
+
+#[test]
+fn caches() {
+    use norse_physical_device::CacheKind;
+
+    let mut properties = common::ryzen_5950x();
+    properties.l1_cache_instruction.size = 0;
+
+    let caches = properties
+        .caches()
+        .map(|cache| (cache.level, cache.kind, cache.properties.size))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        caches,
+        [
+            (1, CacheKind::Data, 32 * 1024),
+            (2, CacheKind::Unified, 512 * 1024),
+            (3, CacheKind::Unified, 64 * 1024 * 1024),
+        ]
+    );
+
+    let largest = properties
+        .caches()
+        .max_by_key(|cache| cache.properties.size)
+        .unwrap();
+    assert_eq!(largest.level, 3);
+}
+
+#[test]
+fn l4_cache() {
+    use norse_physical_device::CacheKind;
+
+    // i7-5775C with 128 MiB eDRAM.
+    let mut properties = common::ryzen_5950x();
+    properties.l4_cache = properties.l3_cache.clone();
+    properties.l4_cache.size = 128 * 1024 * 1024;
+
+    let last = properties.caches().last().unwrap();
+    assert_eq!(
+        (last.level, last.kind, last.properties.size),
+        (4, CacheKind::Unified, 128 * 1024 * 1024)
+    );
+    assert!(properties.to_string().ends_with("L3 64 MiB, L4 128 MiB"));
+    assert!(properties.to_report_string().contains(
+        "[cache.l4]
+size_bytes: 134217728
+"
+    ));
+}
+
+#[test]
+fn vendor_cpuid_string() {
+    for vendor in [
+        Vendor::Intel,
+        Vendor::AMD,
+        Vendor::Hygon,
+        Vendor::Zhaoxin,
+        Vendor::Via,
+    ] {
+        let signature = vendor.cpuid_signature().unwrap();
+        assert_eq!(signature.len(), 12);
+        assert_eq!(Vendor::from_cpuid_string(signature), vendor);
+    }
+    assert_eq!(Vendor::Apple.cpuid_signature(), None);
+    assert_eq!(Vendor::from_cpuid_string("GenuineIotel"), Vendor::Unknown);
+}
+
+#[test]
+fn effective_cache_per_thread() {
+    let mut properties = common::ryzen_5950x();
+    assert_eq!(
+        properties.effective_cache_per_thread(),
+        EffectiveCachePerThread {
+            l2_per_thread: 256 * 1024,
+            l3_per_thread: 4 * 1024 * 1024,
+        }
+    );
+
+    properties.l2_cache.size = 0;
+    properties.l3_cache.shared_by_cores = 0;
+    assert_eq!(
+        properties.effective_cache_per_thread(),
+        EffectiveCachePerThread {
+            l2_per_thread: 0,
+            l3_per_thread: 2 * 1024 * 1024,
+        }
+    );
+}
+
+#[test]
+fn cache_present() {
+    let mut properties = common::ryzen_5950x();
+    assert!(properties.l2_cache.is_present());
+    assert_eq!(
+        properties
+            .l2_cache
+            .total_size_across_cores(properties.physical_cores),
+        8 * 1024 * 1024
+    );
+
+    properties.l2_cache.size = 0;
+    assert!(!properties.l2_cache.is_present());
+    assert_eq!(properties.l2_cache.total_size_across_cores(16), 0);
+}
+
+#[test]
+fn max_vector_bits() {
+    let mut features = PhysicalDeviceFeatures::default();
+    assert_eq!(features.max_vector_bits(), 0);
+
+    features.sse2 = true;
+    assert_eq!(features.max_vector_bits(), 128);
+    features.avx2 = true;
+    assert_eq!(features.max_vector_bits(), 256);
+    features.avx512f = true;
+    assert_eq!(features.max_vector_bits(), 512);
+
+    let mut features = PhysicalDeviceFeatures {
+        neon: true,
+        asimd: true,
+        sve: true,
+        ..Default::default()
+    };
+    assert_eq!(features.max_vector_bits(), 128);
+    features.sve_vector_bits = 256;
+    assert_eq!(features.max_vector_bits(), 256);
+}
+
+#[test]
+fn x86_64_level() {
+    let mut features = PhysicalDeviceFeatures {
+        fpu: true,
+        cmov: true,
+        cx8: true,
+        mmx: true,
+        fxsr: true,
+        sse: true,
+        sse2: true,
+        ..Default::default()
+    };
+    assert_eq!(PhysicalDeviceFeatures::default().x86_64_level(), 0);
+    // Missing SYSCALL
+    assert_eq!(features.x86_64_level(), 0);
+    features.syscall = true;
+    assert_eq!(features.x86_64_level(), 1);
+
+    features.sse3 = true;
+    features.ssse3 = true;
+    features.sse4_1 = true;
+    features.sse4_2 = true;
+    features.popcnt = true;
+    features.cmpxchg16b = true;
+    // Missing LAHF/SAHF
+    assert_eq!(features.x86_64_level(), 1);
+    features.lahf_sahf = true;
+    assert_eq!(features.x86_64_level(), 2);
+
+    features.avx = true;
+    features.avx2 = true;
+    features.bmi1 = true;
+    features.bmi2 = true;
+    features.f16c = true;
+    features.fma = true;
+    features.lzcnt = true;
+    features.movbe = true;
+    assert_eq!(features.x86_64_level(), 3);
+
+    features.avx512f = true;
+    features.avx512bw = true;
+    features.avx512cd = true;
+    features.avx512dq = true;
+    features.avx512vl = true;
+    assert_eq!(features.x86_64_level(), 4);
+    // AVX-512 without v3 doesn't count.
+    features.movbe = false;
+    assert_eq!(features.x86_64_level(), 2);
+}
+
+#[test]
+fn unit_accessors() {
+    let properties = common::ryzen_5950x();
+
+    assert_eq!(properties.physical_core_count(), 16);
+    assert_eq!(properties.logical_core_count(), 32);
+    assert_eq!(properties.l1_data_cache_kib(), 32);
+    assert_eq!(properties.l1_instruction_cache_kib(), 32);
+    assert_eq!(properties.l2_cache_kib(), 512);
+    assert_eq!(properties.l3_cache_mib(), 64);
+    assert_eq!(properties.total_memory_gib(), 64);
+    assert_eq!(properties.available_memory_mib(), 48 * 1024);
+    assert_eq!(properties.cache_line_size(), 64);
+}
+
+#[test]
+fn approximate_model() {
+    let mut properties = common::ryzen_5950x();
+    assert_eq!(
+        properties.approximate_model(),
+        "AMD Ryzen 9 5950X 16-Core Processor"
+    );
+
+    properties.device = String::new();
+    assert_eq!(properties.approximate_model(), "AMD family 0x19 model 0x21");
+
+    properties.vendor = Vendor::Unknown;
+    properties.family = 0;
+    properties.model = 0;
+    assert_eq!(properties.approximate_model(), "Unknown");
+}
+
+#[test]
+fn avx512_frequency_penalty() {
+    let mut properties = common::ryzen_5950x();
+    assert_eq!(properties.avx512_frequency_penalty(), Penalty::Unknown);
+
+    properties.model = 0x61;
+    assert_eq!(properties.avx512_frequency_penalty(), Penalty::None);
+
+    properties.vendor = Vendor::Intel;
+    properties.family = 0x6;
+    properties.model = 0x55;
+    assert_eq!(properties.avx512_frequency_penalty(), Penalty::Heavy);
+    properties.model = 0x6A;
+    assert_eq!(properties.avx512_frequency_penalty(), Penalty::Light);
+    properties.model = 0x8F;
+    assert_eq!(properties.avx512_frequency_penalty(), Penalty::None);
+}
+
+#[test]
+fn cache_total_bytes() {
+    let mut properties = common::ryzen_5950x();
+    assert_eq!(
+        properties.cache_total_bytes(),
+        16 * (32 + 32 + 512) * 1024 + 64 * 1024 * 1024
+    );
+
+    properties.package_count = 2;
+    properties.logical_cores = 64;
+    properties.physical_cores = 32;
+    assert_eq!(
+        properties.cache_total_bytes(),
+        32 * (32 + 32 + 512) * 1024 + 2 * 64 * 1024 * 1024
+    );
+
+    // L2 shared by a cluster of 4 cores without SMT.
+    properties.package_count = 1;
+    properties.l3_cache.size = 0;
+    properties.logical_cores = 4;
+    properties.physical_cores = 4;
+    properties.l1_cache_data.shared_by_cores = 1;
+    properties.l1_cache_instruction.shared_by_cores = 0;
+    properties.l2_cache.shared_by_cores = 4;
+    assert_eq!(
+        properties.cache_total_bytes(),
+        4 * (32 + 32) * 1024 + 512 * 1024
+    );
+}
+
+#[test]
+fn device_class() {
+    let mut properties = common::ryzen_5950x();
+    assert_eq!(properties.device_class(), DeviceClass::Desktop);
+
+    properties.device = "AMD Ryzen 7 5800U with Radeon Graphics".into();
+    properties.physical_cores = 8;
+    properties.tdp_watts = Some(15);
+    assert_eq!(properties.device_class(), DeviceClass::Laptop);
+
+    properties.device = "Intel(R) Core(TM) i7-1165G7 @ 2.80GHz".into();
+    properties.tdp_watts = None;
+    assert_eq!(properties.device_class(), DeviceClass::Laptop);
+
+    properties.device = "AMD EPYC 7763 64-Core Processor".into();
+    properties.physical_cores = 64;
+    properties.tdp_watts = Some(280);
+    assert_eq!(properties.device_class(), DeviceClass::Server);
+
+    // Conflicting signals.
+    properties.tdp_watts = Some(15);
+    assert_eq!(properties.device_class(), DeviceClass::Unknown);
+
+    properties.device = String::new();
+    properties.physical_cores = 4;
+    properties.tdp_watts = None;
+    assert_eq!(properties.device_class(), DeviceClass::Unknown);
+}
+
+#[test]
+fn is_uniform_memory() {
+    let mut properties = common::ryzen_5950x();
+    assert!(properties.is_uniform_memory());
+
+    let mut second = properties.numa_nodes[0].clone();
+    second.node_id = 1;
+    second.logical_cores = second.logical_cores.split_off(16);
+    properties.numa_nodes[0].logical_cores.truncate(16);
+    properties.numa_nodes.push(second);
+    assert!(!properties.is_uniform_memory());
+
+    properties.numa_nodes.clear();
+    assert!(properties.is_uniform_memory());
+}
+
+#[test]
+fn l1_cache_unified() {
+    use norse_physical_device::{CacheKind, PhysicalDeviceCacheProperties};
+
+    let mut properties = common::ryzen_5950x();
+    assert_eq!(properties.l1_effective_data_size(), 32 * 1024);
+
+    let l1_cache_unified = PhysicalDeviceCacheProperties {
+        size: 64 * 1024,
+        line_size: 64,
+        shared_by_cores: 2,
+        ..Default::default()
+    };
+    properties.l1_cache_data = Default::default();
+    properties.l1_cache_instruction = Default::default();
+    properties.l1_cache_unified = Some(l1_cache_unified);
+    assert_eq!(properties.l1_effective_data_size(), 64 * 1024);
+
+    let l1 = properties
+        .caches()
+        .filter(|cache| cache.level == 1)
+        .map(|cache| (cache.kind, cache.properties.size))
+        .collect::<Vec<_>>();
+    assert_eq!(l1, [(CacheKind::Unified, 64 * 1024)]);
+}
+
+#[test]
+fn approx_eq() {
+    let properties = common::ryzen_5950x();
+    assert!(properties.approx_eq(&properties, 0.0));
+
+    let mut other = properties.clone();
+    other.l2_cache.size = 500 * 1024;
+    other.available_memory_bytes = 0;
+    assert!(!properties.approx_eq(&other, 1.0));
+    assert!(properties.approx_eq(&other, 5.0));
+    assert!(other.approx_eq(&properties, 5.0));
+
+    other.device = "AMD Ryzen 9 5950X".into();
+    assert!(!properties.approx_eq(&other, 100.0));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn fingerprint() {
+    let mut properties = common::ryzen_5950x();
+    // More cores than the host, the machine-wide counts are taken from the properties.
+    properties.logical_cores = 4096;
+    properties.physical_cores = 2048;
+    // Pinned, changing the hashed data invalidates on-disk caches keyed by it.
+    assert_eq!(properties.fingerprint(), 0x3700_c062_100a_2670);
+
+    let mut refreshed = properties.clone();
+    refreshed.available_memory_bytes /= 2;
+    refreshed.current_frequency_mhz = 4500;
+    assert_eq!(refreshed.fingerprint(), properties.fingerprint());
+
+    let mut upgraded = properties.clone();
+    upgraded.l3_cache.size *= 2;
+    assert_ne!(upgraded.fingerprint(), properties.fingerprint());
+
+    let mut doubled = properties.clone();
+    doubled.logical_cores *= 2;
+    assert_ne!(doubled.fingerprint(), properties.fingerprint());
+
+    // Restricted by the affinity mask or CPU quota.
+    let system = norse_physical_device::PhysicalDeviceProperties::system();
+    let mut constrained = system.clone();
+    constrained.logical_cores = 1;
+    constrained.physical_cores = 1;
+    assert_eq!(constrained.fingerprint(), system.fingerprint());
+}
+
+#[test]
+fn recommended_thread_count() {
+    let mut properties = common::ryzen_5950x();
+    assert_eq!(properties.recommended_thread_count(Workload::CpuBound), 16);
+    assert_eq!(properties.recommended_thread_count(Workload::IoBound), 32);
+
+    // Clamped to a cgroup quota.
+    properties.logical_cores = 4;
+    properties.physical_cores = 4;
+    assert_eq!(properties.recommended_thread_count(Workload::CpuBound), 4);
+    assert_eq!(properties.recommended_thread_count(Workload::IoBound), 4);
+
+    properties.logical_cores = 0;
+    properties.physical_cores = 0;
+    assert_eq!(properties.recommended_thread_count(Workload::CpuBound), 1);
+    assert_eq!(properties.recommended_thread_count(Workload::IoBound), 1);
+}
+
+#[test]
+fn false_sharing_guard_bytes() {
+    let mut properties = common::ryzen_5950x();
+    // Adjacent line prefetch.
+    assert_eq!(properties.false_sharing_guard_bytes(), 128);
+
+    properties.vendor = Vendor::Arm;
+    assert_eq!(properties.false_sharing_guard_bytes(), 64);
+    properties.l2_cache.line_size = 128;
+    assert_eq!(properties.false_sharing_guard_bytes(), 128);
+
+    for cache in [
+        &mut properties.l1_cache_data,
+        &mut properties.l1_cache_instruction,
+        &mut properties.l2_cache,
+        &mut properties.l3_cache,
+    ] {
+        cache.line_size = 0;
+    }
+    assert_eq!(properties.false_sharing_guard_bytes(), 128);
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use norse_physical_device::{
+    DeviceRequirements, PhysicalDevice, PhysicalDeviceFeatures, UnmetRequirement,
+};
+
+#[test]
+fn satisfies() {
+    let device = PhysicalDevice::from_properties(common::ryzen_5950x());
+
+    assert!(device.satisfies(&DeviceRequirements::default()));
+    assert!(device.satisfies(&DeviceRequirements {
+        min_physical_cores: Some(4),
+        min_total_memory_bytes: Some(16 << 30),
+        ..Default::default()
+    }));
+}
+
+#[test]
+fn check() {
+    let device = PhysicalDevice::from_properties(common::ryzen_5950x());
+    let requirements = DeviceRequirements {
+        min_physical_cores: Some(64),
+        min_logical_cores: Some(32),
+        min_total_memory_bytes: Some(128 << 30),
+        features: PhysicalDeviceFeatures {
+            avx2: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let unmet = device.check(&requirements);
+    assert_eq!(
+        unmet,
+        [
+            UnmetRequirement::PhysicalCores {
+                required: 64,
+                available: 16
+            },
+            UnmetRequirement::TotalMemory {
+                required: 128 << 30,
+                available: 64 << 30
+            },
+            UnmetRequirement::Feature("avx2"),
+        ]
+    );
+    assert_eq!(
+        unmet[1].to_string(),
+        "128 GiB memory required, 64 GiB available"
+    );
+    assert!(!device.satisfies(&requirements));
+}
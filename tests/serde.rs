@@ -0,0 +1,39 @@
+#![cfg(feature = "serde")]
+
+mod common;
+
+use norse_physical_device::{Hypervisor, PhysicalDeviceProperties, Vendor};
+
+#[test]
+fn roundtrip_properties() {
+    let properties = common::ryzen_5950x();
+
+    let json = serde_json::to_string(&properties).unwrap();
+    let parsed: PhysicalDeviceProperties = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, properties);
+}
+
+#[test]
+fn vendor_strings() {
+    assert_eq!(serde_json::to_string(&Vendor::Intel).unwrap(), "\"intel\"");
+    assert_eq!(serde_json::to_string(&Vendor::AMD).unwrap(), "\"amd\"");
+    assert_eq!(
+        serde_json::to_string(&Vendor::Unknown).unwrap(),
+        "\"unknown\""
+    );
+
+    let vendor: Vendor = serde_json::from_str("\"unknown\"").unwrap();
+    assert_eq!(vendor, Vendor::Unknown);
+    let vendor: Vendor = serde_json::from_str("\"loongson\"").unwrap();
+    assert_eq!(vendor, Vendor::Unknown);
+}
+
+#[test]
+fn roundtrip_hypervisor() {
+    let mut properties = common::ryzen_5950x();
+    properties.hypervisor = Some(Hypervisor::Unknown("ACRNACRNACRN".to_string()));
+
+    let json = serde_json::to_string(&properties).unwrap();
+    let parsed: PhysicalDeviceProperties = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, properties);
+}